@@ -0,0 +1,20 @@
+//! A git merge driver for debian/changelog files.
+//!
+//! Install it with `cargo install debian-changelog`, and configure it with:
+//!
+//! ```text
+//! git config merge.debian-changelog.driver "debian-changelog-merge-driver %O %A %B"
+//! echo "debian/changelog merge=debian-changelog" >> .gitattributes
+//! ```
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() != 4 {
+        eprintln!("usage: {} BASE OURS THEIRS", args[0]);
+        std::process::exit(2);
+    }
+    if !debian_changelog::merge::merge_files(&args[1], &args[2], &args[3])? {
+        std::process::exit(1);
+    }
+    Ok(())
+}
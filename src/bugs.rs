@@ -0,0 +1,188 @@
+//! Extraction of bug references ("Closes: #XXXXXX" and "LP: #XXXXXX") from changes.
+
+use crate::{ChangeLog, Entry};
+use lazy_regex::{Lazy, Regex};
+
+/// A statement closing Debian bugs, as in Debian policy, e.g. "Closes: #123, Bug#456".
+pub(crate) static CLOSES: Lazy<Regex> =
+    lazy_regex::lazy_regex!(r"(?i)closes:\s*(?:bug)?\#?\s?\d+(?:,\s*(?:bug)?\#?\s?\d+)*");
+
+/// A statement closing Launchpad bugs, as recognized by dpkg, e.g. "LP: #123, #456".
+pub(crate) static LAUNCHPAD_BUGS: Lazy<Regex> =
+    lazy_regex::lazy_regex!(r"(?i)lp:\s+\#\d+(?:,\s*\#\d+)*");
+
+/// A reference to a bug in the changes of an entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BugReference {
+    /// The bug number.
+    pub bug: u32,
+    /// The index of the line in [`Entry::change_lines`] the bug number appears on.
+    pub line: usize,
+    /// The byte range of the bug number, including any "#" or "Bug#" prefix, within that line.
+    pub range: std::ops::Range<usize>,
+}
+
+/// Find references in text, using a regex for the whole statement and one for the bugs in it.
+fn find_references(text: &str, statement: &Lazy<Regex>, bug: &Lazy<Regex>) -> Vec<BugReference> {
+    let mut ret = vec![];
+    for m in statement.find_iter(text) {
+        for c in bug.captures_iter(m.as_str()) {
+            let whole = c.get(0).unwrap();
+            let Ok(number) = c[1].parse() else {
+                continue;
+            };
+            let leading = whole.len() - whole.as_str().trim_start().len();
+            let start = m.start() + whole.start() + leading;
+            let end = m.start() + whole.end();
+            let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+            ret.push(BugReference {
+                bug: number,
+                line: text[..start].matches('\n').count(),
+                range: start - line_start..end - line_start,
+            });
+        }
+    }
+    ret
+}
+
+/// Find the Debian bugs closed in a text, using the regex from Debian policy.
+///
+/// The text may span multiple lines; line numbers count from 0.
+///
+/// # Example
+///
+/// ```
+/// let refs = debian_changelog::bugs::find_closes("* Fix crash. Closes: #123, Bug#456");
+/// assert_eq!(refs.iter().map(|r| r.bug).collect::<Vec<_>>(), vec![123, 456]);
+/// assert_eq!(refs[1].range, 27..34);
+/// ```
+pub fn find_closes(text: &str) -> Vec<BugReference> {
+    find_references(
+        text,
+        &CLOSES,
+        lazy_regex::regex!(r"(?i)(?:bug)?\#?\s?(\d+)"),
+    )
+}
+
+/// Find the Launchpad bugs closed in a text.
+///
+/// The text may span multiple lines; line numbers count from 0.
+pub fn find_launchpad_bugs(text: &str) -> Vec<BugReference> {
+    find_references(text, &LAUNCHPAD_BUGS, lazy_regex::regex!(r"\#(\d+)"))
+}
+
+impl Entry {
+    /// Return the Debian bugs closed by this entry.
+    pub fn closes(&self) -> Vec<BugReference> {
+        find_closes(self.change_lines().collect::<Vec<_>>().join("\n").as_str())
+    }
+
+    /// Return the Launchpad bugs closed by this entry.
+    pub fn launchpad_bugs(&self) -> Vec<BugReference> {
+        find_launchpad_bugs(self.change_lines().collect::<Vec<_>>().join("\n").as_str())
+    }
+}
+
+impl ChangeLog {
+    /// Return the Debian bugs closed by any of the entries, in ascending order.
+    pub fn closes(&self) -> Vec<u32> {
+        let mut ret = self
+            .entries()
+            .flat_map(|e| e.closes())
+            .map(|r| r.bug)
+            .collect::<Vec<_>>();
+        ret.sort();
+        ret.dedup();
+        ret
+    }
+
+    /// Return the Launchpad bugs closed by any of the entries, in ascending order.
+    pub fn launchpad_bugs(&self) -> Vec<u32> {
+        let mut ret = self
+            .entries()
+            .flat_map(|e| e.launchpad_bugs())
+            .map(|r| r.bug)
+            .collect::<Vec<_>>();
+        ret.sort();
+        ret.dedup();
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bugs(refs: Vec<BugReference>) -> Vec<u32> {
+        refs.into_iter().map(|r| r.bug).collect()
+    }
+
+    #[test]
+    fn test_find_closes() {
+        assert_eq!(bugs(find_closes("Closes: #123, #456")), vec![123, 456]);
+        assert_eq!(bugs(find_closes("closes: bug#123,bug 456")), vec![123, 456]);
+        assert_eq!(bugs(find_closes("CLOSES:123")), vec![123]);
+        assert_eq!(bugs(find_closes("Closes: Bug#123")), vec![123]);
+        assert_eq!(bugs(find_closes("Nothing to see #123")), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_find_launchpad_bugs() {
+        assert_eq!(
+            bugs(find_launchpad_bugs("Fix it. LP: #123, #456 Closes: #789")),
+            vec![123, 456]
+        );
+        assert_eq!(bugs(find_launchpad_bugs("LP: 123")), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_position() {
+        let refs = find_closes("* Fix crash.\n  Closes: #123,\n  #456");
+        assert_eq!(
+            refs,
+            vec![
+                BugReference {
+                    bug: 123,
+                    line: 1,
+                    range: 10..14,
+                },
+                BugReference {
+                    bug: 456,
+                    line: 2,
+                    range: 2..6,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_entry() {
+        let cl: ChangeLog = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release. Closes: #1039011
+  * Fix crash on startup. LP: #2000, #2001
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+breezy (3.3.3-1) unstable; urgency=low
+
+  * Drop dependency on cython3-dbg. closes: bug#1040544, #1039011
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 17 Jun 2023 14:58:57 +0100
+"#
+        .parse()
+        .unwrap();
+        let entry = cl.entries().next().unwrap();
+        assert_eq!(
+            entry.closes(),
+            vec![BugReference {
+                bug: 1039011,
+                line: 0,
+                range: 32..40,
+            }]
+        );
+        assert_eq!(bugs(entry.launchpad_bugs()), vec![2000, 2001]);
+        assert_eq!(cl.closes(), vec![1039011, 1040544]);
+        assert_eq!(cl.launchpad_bugs(), vec![2000, 2001]);
+    }
+}
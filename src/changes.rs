@@ -307,6 +307,41 @@ pub fn add_change_for_author(
     author_name: &str,
     change: Vec<&str>,
     default_author: Option<(String, String)>,
+) {
+    add_change_for_author_with(
+        changes,
+        author_name,
+        change,
+        default_author,
+        &crate::textwrap::Wrapper::new(),
+    )
+}
+
+/// Add a change to the list of changes, attributed to a specific author, rewrapping it with
+/// `wrapper`.
+///
+/// See [`add_change_for_author`].
+///
+/// # Example
+///
+/// ```
+/// use debian_changelog::textwrap::Wrapper;
+/// let mut changes = vec![];
+/// debian_changelog::changes::add_change_for_author_with(
+///     &mut changes,
+///     "Author 1",
+///     vec!["* Change 1 with some more words"],
+///     None,
+///     &Wrapper::new().width(20),
+/// );
+/// assert_eq!(changes, vec!["* Change 1 with some", "  more words"]);
+/// ```
+pub fn add_change_for_author_with(
+    changes: &mut Vec<String>,
+    author_name: &str,
+    change: Vec<&str>,
+    default_author: Option<(String, String)>,
+    wrapper: &crate::textwrap::Wrapper,
 ) {
     let by_author = changes_by_author(changes.iter().map(|s| s.as_str())).collect::<Vec<_>>();
 
@@ -331,7 +366,12 @@ pub fn add_change_for_author(
         }
     }
 
-    changes.extend(crate::textwrap::rewrap_changes(change.into_iter()).map(|s| s.to_string()));
+    changes.extend(
+        wrapper
+            .rewrap_changes_lenient(change.into_iter())
+            .into_iter()
+            .map(|s| s.to_string()),
+    );
 }
 
 #[cfg(test)]
@@ -364,6 +404,30 @@ mod add_change_for_author_tests {
         );
         assert_eq!(changes, vec!["[ Author 1 ]", "* Change 1"]);
     }
+
+    #[test]
+    fn test_width() {
+        let change = vec!["* Change 1 with a description that is a bit longer than usual"];
+        let mut changes = vec![];
+        add_change_for_author(&mut changes, "Author 1", change.clone(), None);
+        assert_eq!(changes, change);
+
+        let mut changes = vec![];
+        add_change_for_author_with(
+            &mut changes,
+            "Author 1",
+            change,
+            None,
+            &crate::textwrap::Wrapper::new().width(40),
+        );
+        assert_eq!(
+            changes,
+            vec![
+                "* Change 1 with a description that is a",
+                "  bit longer than usual"
+            ]
+        );
+    }
 }
 
 /// Find additional authors from a changelog entry
@@ -461,6 +525,340 @@ fn test_find_thanks() {
     );
 }
 
+/// The bullet that a change starts with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bullet {
+    /// `*`, usually used for top-level changes
+    Asterisk,
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+}
+
+impl Bullet {
+    /// Return the character for the bullet.
+    pub fn as_char(&self) -> char {
+        match self {
+            Bullet::Asterisk => '*',
+            Bullet::Plus => '+',
+            Bullet::Minus => '-',
+        }
+    }
+
+    pub(crate) fn from_char(c: char) -> Option<Self> {
+        match c {
+            '*' => Some(Bullet::Asterisk),
+            '+' => Some(Bullet::Plus),
+            '-' => Some(Bullet::Minus),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Bullet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.as_char())
+    }
+}
+
+/// A change from a changelog entry, with its wrapped lines joined back together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogicalChange {
+    /// The bullet the change starts with
+    pub bullet: Bullet,
+
+    /// The nesting level: 0 for top-level changes, 1 for changes nested below those, etc.
+    pub depth: usize,
+
+    /// The number of columns before the bullet
+    pub indent: usize,
+
+    /// The unwrapped text of the change, without the bullet.
+    ///
+    /// Lines that follow a line ending in a colon, or that are indented differently from
+    /// the line before them, are kept on separate lines, along with any indentation beyond
+    /// that of the first line.
+    pub text: String,
+}
+
+impl LogicalChange {
+    /// Wrap the change again.
+    pub fn wrap(&self, wrapper: &crate::textwrap::Wrapper) -> Vec<String> {
+        let prefix_width = self.indent + 2;
+        let mut initial_indent = format!("{}{} ", " ".repeat(self.indent), self.bullet);
+        let mut lines = vec![];
+        for (i, paragraph) in self.text.split('\n').enumerate() {
+            let text = paragraph.trim_start_matches(' ');
+            if i > 0 {
+                initial_indent = " ".repeat(prefix_width + paragraph.len() - text.len());
+            }
+            let subsequent_indent = " ".repeat(initial_indent.len());
+            lines.extend(
+                wrapper
+                    .wrap(text, Some(&initial_indent), Some(&subsequent_indent))
+                    .into_iter()
+                    .map(|l| l.into_owned()),
+            );
+        }
+        lines
+    }
+}
+
+/// A line from the changes of a changelog entry, after unwrapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogicalLine {
+    /// A change, which may have spanned several lines
+    Change(LogicalChange),
+
+    /// A line that is not part of a change, like a blank line or a section header
+    /// (`[ Author ]`), or a line from a change that is not indented consistently.
+    Verbatim(String),
+}
+
+// Join the lines of a single change, or return None if the lines are not indented to match
+// the bullet point.
+//
+// Lines are joined if they are indented as far as the line before them, unless that line ends
+// in a colon. Lines that are indented further start a new paragraph, which keeps its
+// indentation.
+fn unwrap_change(lines: &[&str]) -> Option<(Bullet, usize, String)> {
+    let (prefix, indent, bullet) = regex_captures!(r"^( *)([\+\-\*]) ", lines[0])?;
+    let bullet = Bullet::from_char(bullet.chars().next()?)?;
+    let subsequent_indent = " ".repeat(prefix.len());
+    let indentation = |line: &str| line.len() - line.trim_start_matches(' ').len();
+
+    let mut last = &lines[0][prefix.len()..];
+    let mut text = last.to_string();
+    for line in &lines[1..] {
+        let rest = line.strip_prefix(subsequent_indent.as_str())?;
+        if indentation(rest) == indentation(last) && !last.ends_with(':') {
+            text.push(' ');
+            text.push_str(rest.trim_start_matches(' '));
+        } else {
+            text.push('\n');
+            text.push_str(rest);
+        }
+        last = rest;
+    }
+    Some((bullet, indent.len(), text))
+}
+
+/// Join the wrapped lines of changes back into logical changes.
+///
+/// Wrapping the result again with [`wrap_changes`] reproduces the changes, wrapped to the
+/// given width.
+///
+/// # Example
+///
+/// ```
+/// use debian_changelog::changes::{unwrap_changes, Bullet, LogicalChange, LogicalLine};
+///
+/// let changes = unwrap_changes(
+///     vec!["* New upstream release.", "  + Fixes a crash when", "    starting up."].into_iter(),
+/// );
+/// assert_eq!(
+///     changes[1],
+///     LogicalLine::Change(LogicalChange {
+///         bullet: Bullet::Plus,
+///         depth: 1,
+///         indent: 2,
+///         text: "Fixes a crash when starting up.".to_string(),
+///     })
+/// );
+/// ```
+pub fn unwrap_changes<'a>(changes: impl Iterator<Item = &'a str>) -> Vec<LogicalLine> {
+    let mut ret = vec![];
+    // Indentation of the changes that the next change may be nested below
+    let mut parents: Vec<usize> = vec![];
+    for (is_change, lines) in crate::textwrap::group_changes(changes) {
+        match is_change.then(|| unwrap_change(&lines)).flatten() {
+            Some((bullet, indent, text)) => {
+                while parents.last().is_some_and(|i| *i >= indent) {
+                    parents.pop();
+                }
+                ret.push(LogicalLine::Change(LogicalChange {
+                    bullet,
+                    depth: parents.len(),
+                    indent,
+                    text,
+                }));
+                parents.push(indent);
+            }
+            None => {
+                if !is_change && !lines[0].trim().is_empty() {
+                    // Section headers start a new list of changes
+                    parents.clear();
+                }
+                ret.extend(
+                    lines
+                        .into_iter()
+                        .map(|l| LogicalLine::Verbatim(l.to_string())),
+                );
+            }
+        }
+    }
+    ret
+}
+
+/// Wrap logical changes, as returned by [`unwrap_changes`], to the given width.
+pub fn wrap_changes(changes: &[LogicalLine], width: Option<usize>) -> Vec<String> {
+    let wrapper =
+        crate::textwrap::Wrapper::new().width(width.unwrap_or(crate::textwrap::DEFAULT_WIDTH));
+    changes
+        .iter()
+        .flat_map(|line| match line {
+            LogicalLine::Change(change) => change.wrap(&wrapper),
+            LogicalLine::Verbatim(line) => vec![line.clone()],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod unwrap_changes_tests {
+    use super::*;
+
+    fn change(bullet: Bullet, depth: usize, indent: usize, text: &str) -> LogicalLine {
+        LogicalLine::Change(LogicalChange {
+            bullet,
+            depth,
+            indent,
+            text: text.to_string(),
+        })
+    }
+
+    const CHANGES: &[&str] = &[
+        "[ Jane Example ]",
+        "* New upstream release. This is a change that",
+        "  spans a few lines, and needs to be joined",
+        "  back together.",
+        "  - Drop patches applied upstream:",
+        "    + fix-crash.patch",
+        "    + fix-typo.patch",
+        "",
+        "[ Joe Example ]",
+        "* Fix the build with the new compiler,",
+        "  which Debian CI found to fail on these",
+        "  architectures:",
+        "    armel, armhf, mipsel and s390x.",
+    ];
+
+    #[test]
+    fn test_unwrap() {
+        assert_eq!(
+            unwrap_changes(CHANGES.iter().copied()),
+            vec![
+                LogicalLine::Verbatim("[ Jane Example ]".to_string()),
+                change(
+                    Bullet::Asterisk,
+                    0,
+                    0,
+                    "New upstream release. This is a change that spans a few lines, and needs to be joined back together."
+                ),
+                change(Bullet::Minus, 1, 2, "Drop patches applied upstream:"),
+                change(Bullet::Plus, 2, 4, "fix-crash.patch"),
+                change(Bullet::Plus, 2, 4, "fix-typo.patch"),
+                LogicalLine::Verbatim("".to_string()),
+                LogicalLine::Verbatim("[ Joe Example ]".to_string()),
+                change(
+                    Bullet::Asterisk,
+                    0,
+                    0,
+                    "Fix the build with the new compiler, which Debian CI found to fail on these architectures:\n  armel, armhf, mipsel and s390x."
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let changes = unwrap_changes(CHANGES.iter().copied());
+        assert_eq!(wrap_changes(&changes, Some(48)), CHANGES);
+        for width in [20, 40, 78] {
+            let wrapped = wrap_changes(&changes, Some(width));
+            let unwrapped = unwrap_changes(wrapped.iter().map(|l| l.as_str()));
+            assert_eq!(unwrapped, changes);
+            assert_eq!(
+                wrap_changes(&unwrapped, Some(width)),
+                wrapped,
+                "width {}",
+                width
+            );
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_capitalized() {
+        let changes = unwrap_changes(
+            ["* Rebuild the package against the new upstream version of Debian tools."].into_iter(),
+        );
+        let wrapped = wrap_changes(&changes, Some(30));
+        assert_eq!(
+            wrapped,
+            vec![
+                "* Rebuild the package against",
+                "  the new upstream version of",
+                "  Debian tools."
+            ]
+        );
+        assert_eq!(unwrap_changes(wrapped.iter().map(|l| l.as_str())), changes);
+    }
+
+    #[test]
+    fn test_small_widths() {
+        let changes = unwrap_changes(CHANGES.iter().copied());
+        for width in 0..=4 {
+            let wrapped = wrap_changes(&changes, Some(width));
+            assert!(wrapped.len() >= CHANGES.len(), "width {}", width);
+        }
+    }
+
+    #[test]
+    fn test_inconsistent_indentation() {
+        assert_eq!(
+            unwrap_changes(vec!["* Foo", " bar", "no bullet"].into_iter()),
+            vec![
+                LogicalLine::Verbatim("* Foo".to_string()),
+                LogicalLine::Verbatim(" bar".to_string()),
+                LogicalLine::Verbatim("no bullet".to_string()),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn unwrap_reverses_wrap(
+            changes in prop::collection::vec(
+                (any::<bool>(), prop::collection::vec("[A-Za-z0-9][A-Za-z0-9,.()'-]{0,9}", 1..20)),
+                1..5,
+            ),
+            width in 20usize..80,
+        ) {
+            let changes = changes
+                .into_iter()
+                .enumerate()
+                .map(|(i, (nested, words))| {
+                    let depth = (nested && i > 0) as usize;
+                    LogicalLine::Change(LogicalChange {
+                        bullet: if depth > 0 { Bullet::Plus } else { Bullet::Asterisk },
+                        depth,
+                        indent: 2 * depth,
+                        text: words.join(" "),
+                    })
+                })
+                .collect::<Vec<_>>();
+            let wrapped = wrap_changes(&changes, Some(width));
+            prop_assert_eq!(unwrap_changes(wrapped.iter().map(|l| l.as_str())), changes);
+        }
+    }
+}
+
 /// Check if all lines in a changelog entry are prefixed with a sha.
 ///
 /// This is generally done by gbp-dch(1).
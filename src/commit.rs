@@ -0,0 +1,334 @@
+//! Generation of commit messages from changes to a changelog, like debcommit does.
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::commit::{commit_message, CommitMessageOptions};
+//!
+//! let old = r#"foo (1.0-2) UNRELEASED; urgency=low
+//!
+//!   * Fix a bug.
+//!
+//!  -- Joe Example <joe@example.com>  Tue, 05 Sep 2023 18:13:45 -0500
+//! "#;
+//! let new = old.replace("  * Fix a bug.\n", "  * Fix a bug.\n  * Fix a crash. Closes: #123\n");
+//!
+//! let options = CommitMessageOptions {
+//!     closes_trailer: true,
+//!     ..Default::default()
+//! };
+//! let message = commit_message(old, &new, &options).unwrap();
+//! assert_eq!(message.summary, "Fix a crash. Closes: #123");
+//! assert_eq!(message.to_string(), "Fix a crash. Closes: #123\n\nCloses: #123\n");
+//! ```
+
+use crate::changes::{changes_by_author, strip_for_commit_message};
+use crate::diff::common_subsequence;
+use crate::ChangeLog;
+
+/// Options for [`commit_message`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CommitMessageOptions {
+    /// Add a `Closes:` trailer for the bugs closed by the new changes.
+    pub closes_trailer: bool,
+    /// Add a `Gbp-Dch: Ignore` trailer, so that gbp dch does not add the commit to the
+    /// changelog again.
+    pub gbp_dch_trailer: bool,
+    /// Keep the `[sha]` prefixes that gbp dch adds to changes.
+    pub keep_sha_prefixes: bool,
+}
+
+/// A commit message.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CommitMessage {
+    /// The first line of the message.
+    pub summary: String,
+    /// The lines following the summary, separated from it by a blank line.
+    pub body: Vec<String>,
+    /// Trailers, as (key, value) pairs.
+    pub trailers: Vec<(String, String)>,
+}
+
+impl std::fmt::Display for CommitMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}", self.summary)?;
+        if !self.body.is_empty() {
+            writeln!(f)?;
+            for line in &self.body {
+                writeln!(f, "{}", line)?;
+            }
+        }
+        if !self.trailers.is_empty() {
+            writeln!(f)?;
+            for (key, value) in &self.trailers {
+                writeln!(f, "{}: {}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mark the lines in `new` that are not part of the longest common subsequence with `old`.
+fn added_lines(old: &[String], new: &[String]) -> Vec<bool> {
+    let mut added = vec![true; new.len()];
+    for (_, j) in common_subsequence(old, new) {
+        added[j] = false;
+    }
+    added
+}
+
+/// Find the changes that were added to the top entry of a changelog.
+///
+/// Changes that were modified count as added. Returns the changes as (author, lines) pairs,
+/// where the author is the title of the section the change is in, if any.
+pub fn new_changes(old: &str, new: &str) -> Vec<(Option<String>, Vec<String>)> {
+    let parse = |text: &str| {
+        ChangeLog::read_relaxed(text.as_bytes())
+            .ok()
+            .map(|(c, _)| c)
+    };
+    let Some(top) = parse(new).and_then(|c| c.entries().next()) else {
+        return vec![];
+    };
+    let new_lines = top.change_lines().collect::<Vec<_>>();
+    let old_lines = top
+        .version()
+        .and_then(|v| parse(old)?.entry_by_version(&v))
+        .map(|e| e.change_lines().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let added = added_lines(&old_lines, &new_lines);
+
+    // change_lines never starts with an empty line, so the line numbers index into new_lines.
+    changes_by_author(new_lines.iter().map(|l| l.as_str()))
+        .filter(|(_, linenos, _)| linenos.iter().any(|i| added[*i]))
+        .map(|(author, _, lines)| {
+            (
+                author.map(|a| a.to_string()),
+                lines.into_iter().map(|l| l.to_string()).collect(),
+            )
+        })
+        .collect()
+}
+
+/// Remove a gbp dch `[sha]` prefix from the first line of a change.
+fn strip_sha_prefix(line: &str) -> String {
+    match lazy_regex::regex_captures!(r"^(\* )\[[0-9a-f]{7,40}\] (.*)$", line) {
+        Some((_, bullet, rest)) => format!("{}{}", bullet, rest),
+        None => line.to_string(),
+    }
+}
+
+/// Generate a commit message for the changes made to the top entry of a changelog.
+///
+/// # Arguments
+/// * `old` - The text of the changelog before the changes
+/// * `new` - The text of the changelog after the changes
+/// * `options` - Options for the message
+///
+/// # Returns
+/// The commit message, or None if no changes were added.
+pub fn commit_message(
+    old: &str,
+    new: &str,
+    options: &CommitMessageOptions,
+) -> Option<CommitMessage> {
+    let mut changes = new_changes(old, new);
+    if changes.is_empty() {
+        return None;
+    }
+    if !options.keep_sha_prefixes {
+        for (_, lines) in changes.iter_mut() {
+            if let Some(first) = lines.first_mut() {
+                *first = strip_sha_prefix(first);
+            }
+        }
+    }
+
+    let mut lines = vec![];
+    let summary;
+    if changes.len() == 1 {
+        // A single change becomes a single line, without the bullet.
+        let text = changes[0]
+            .1
+            .iter()
+            .map(|l| l.trim())
+            .collect::<Vec<_>>()
+            .join(" ");
+        summary = strip_for_commit_message(vec![text.as_str()])[0].to_string();
+    } else {
+        // Several changes are listed in the body.
+        summary = "Update changelog".to_string();
+        // Only keep the author sections if the changes are by more than one author.
+        let multiple_authors = changes.iter().any(|(a, _)| a != &changes[0].0);
+        let mut author = None;
+        for (change_author, change) in &changes {
+            if multiple_authors && change_author.is_some() && change_author != &author {
+                lines.push(format!("[ {} ]", change_author.as_ref().unwrap()));
+                author = change_author.clone();
+            }
+            lines.extend(change.iter().cloned());
+        }
+        lines = strip_for_commit_message(lines.iter().map(|l| l.as_str()).collect())
+            .into_iter()
+            .map(|l| l.to_string())
+            .collect();
+    }
+
+    let mut trailers = vec![];
+    if options.closes_trailer {
+        let text = changes
+            .iter()
+            .flat_map(|(_, lines)| lines.iter().map(|l| l.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut bugs = crate::bugs::find_closes(&text)
+            .into_iter()
+            .map(|r| r.bug)
+            .collect::<Vec<_>>();
+        bugs.sort();
+        bugs.dedup();
+        if !bugs.is_empty() {
+            trailers.push((
+                "Closes".to_string(),
+                bugs.iter()
+                    .map(|b| format!("#{}", b))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ));
+        }
+    }
+    if options.gbp_dch_trailer {
+        trailers.push(("Gbp-Dch".to_string(), "Ignore".to_string()));
+    }
+
+    Some(CommitMessage {
+        summary,
+        body: lines,
+        trailers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = r#"foo (1.0-2) UNRELEASED; urgency=low
+
+  * A.
+
+ -- Joe Example <joe@example.com>  Tue, 05 Sep 2023 18:13:45 -0500
+
+foo (1.0-1) unstable; urgency=low
+
+  * Initial release.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+
+    fn message(old: &str, new: &str, options: &CommitMessageOptions) -> Option<String> {
+        commit_message(old, new, options).map(|m| m.to_string())
+    }
+
+    #[test]
+    fn test_no_changes() {
+        assert_eq!(message(OLD, OLD, &Default::default()), None);
+    }
+
+    #[test]
+    fn test_single_change() {
+        let new = OLD.replace(
+            "  * A.\n",
+            "  * A.\n  * B is a change\n    that spans lines.\n",
+        );
+        assert_eq!(
+            message(OLD, &new, &Default::default()),
+            Some("B is a change that spans lines.\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_multiple_changes() {
+        let new = OLD.replace("  * A.\n", "  * B.\n  * A.\n  * C.\n");
+        assert_eq!(
+            message(OLD, &new, &Default::default()),
+            Some("Update changelog\n\n* B.\n* C.\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_new_entry() {
+        let new = format!(
+            "foo (1.0-3) UNRELEASED; urgency=low\n\n  * B.\n\n -- Joe Example <joe@example.com>  Tue, 05 Sep 2023 18:13:45 -0500\n\n{}",
+            OLD
+        );
+        assert_eq!(
+            commit_message(OLD, &new, &Default::default())
+                .unwrap()
+                .summary,
+            "B."
+        );
+    }
+
+    #[test]
+    fn test_authors() {
+        let old = OLD.replace("  * A.\n", "  [ Jane ]\n  * A.\n\n  [ Joe ]\n  * B.\n");
+        // A single author's changes are not prefixed with the section header
+        let new = old.replace("  * B.\n", "  * B.\n  * C.\n");
+        assert_eq!(
+            message(&old, &new, &Default::default()),
+            Some("C.\n".to_string())
+        );
+        let new = new.replace("  * A.\n", "  * A.\n  * D.\n");
+        assert_eq!(
+            message(&old, &new, &Default::default()),
+            Some("Update changelog\n\n[ Jane ]\n* D.\n[ Joe ]\n* C.\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_trailers() {
+        let options = CommitMessageOptions {
+            closes_trailer: true,
+            gbp_dch_trailer: true,
+            ..Default::default()
+        };
+        let new = OLD.replace(
+            "  * A.\n",
+            "  * A.\n  * B. Closes: #2\n  * C. Closes: #1, #2\n",
+        );
+        assert_eq!(
+            message(OLD, &new, &options),
+            Some(
+                "Update changelog\n\n* B. Closes: #2\n* C. Closes: #1, #2\n\nCloses: #1, #2\nGbp-Dch: Ignore\n"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_sha_prefixes() {
+        let old = OLD.replace("  * A.\n", "");
+        let new = OLD.replace(
+            "  * A.\n",
+            "  * [abcdef1] Fix a bug.\n  * [1234567] Fix a crash.\n",
+        );
+        assert_eq!(
+            message(&old, &new, &Default::default()),
+            Some("Update changelog\n\n* Fix a bug.\n* Fix a crash.\n".to_string())
+        );
+        assert_eq!(
+            message(
+                &old,
+                &new,
+                &CommitMessageOptions {
+                    keep_sha_prefixes: true,
+                    ..Default::default()
+                }
+            ),
+            Some(
+                "Update changelog\n\n* [abcdef1] Fix a bug.\n* [1234567] Fix a crash.\n"
+                    .to_string()
+            )
+        );
+    }
+}
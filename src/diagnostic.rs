@@ -0,0 +1,264 @@
+//! Position-aware diagnostics for problems found while parsing a changelog.
+
+use crate::SyntaxKind;
+use rowan::{TextRange, TextSize};
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The input could not be parsed as expected; the tree contains an ERROR node.
+    Error,
+    /// The input was parsed, but contains a value that is not valid.
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// The kind of problem that was found.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    /// A specific token was expected, but something else (or nothing) was found.
+    Expected {
+        expected: SyntaxKind,
+        found: Option<SyntaxKind>,
+    },
+    /// A token was found where none was expected.
+    UnexpectedToken(Option<SyntaxKind>),
+    /// The header is missing a distribution or the semicolon before the metadata.
+    ExpectedDistribution,
+    /// The header ended before the metadata, or has trailing garbage.
+    ExpectedSemicolonOrNewline,
+    /// A metadata entry is missing its key.
+    ExpectedMetadataKey,
+    /// A metadata entry is missing its value.
+    ExpectedMetadataValue,
+    /// An indented line in the body has no contents.
+    ExpectedDetail,
+    /// The footer does not start with " -- ".
+    ExpectedFooterDashes,
+    /// The email address and the date in the footer are not separated by two spaces.
+    ExpectedTwoSpaces,
+    /// The file ended in the middle of an entry.
+    UnexpectedEndOfFile,
+    /// A version that is not a valid Debian version.
+    InvalidVersion(String),
+    /// An urgency value that is not recognized.
+    InvalidUrgency(String),
+}
+
+fn describe(kind: &Option<SyntaxKind>) -> String {
+    match kind {
+        Some(kind) => format!("{:?}", kind),
+        None => "end of file".to_string(),
+    }
+}
+
+impl std::fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DiagnosticKind::Expected { expected, found } => {
+                write!(f, "expected {:?}, got {}", expected, describe(found))
+            }
+            DiagnosticKind::UnexpectedToken(found) => {
+                write!(f, "unexpected {}", describe(found))
+            }
+            DiagnosticKind::ExpectedDistribution => {
+                f.write_str("expected distribution or semicolon")
+            }
+            DiagnosticKind::ExpectedSemicolonOrNewline => {
+                f.write_str("expected semicolon or newline")
+            }
+            DiagnosticKind::ExpectedMetadataKey => f.write_str("expected metadata key"),
+            DiagnosticKind::ExpectedMetadataValue => f.write_str("expected metadata value"),
+            DiagnosticKind::ExpectedDetail => f.write_str("expected detail"),
+            DiagnosticKind::ExpectedFooterDashes => f.write_str("expected \" -- \""),
+            DiagnosticKind::ExpectedTwoSpaces => {
+                f.write_str("expected two spaces between email and date")
+            }
+            DiagnosticKind::UnexpectedEndOfFile => f.write_str("unexpected end of file"),
+            DiagnosticKind::InvalidVersion(version) => write!(f, "invalid version: {}", version),
+            DiagnosticKind::InvalidUrgency(urgency) => write!(f, "invalid urgency: {}", urgency),
+        }
+    }
+}
+
+/// A problem found in a changelog, with its location.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub severity: Severity,
+    /// The byte range in the source text.
+    pub range: TextRange,
+    /// The line the range starts on, counting from 1.
+    pub line: usize,
+    /// The column the range starts at, in characters and counting from 1.
+    pub column: usize,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic, computing the line and column from the source text.
+    pub fn new(kind: DiagnosticKind, severity: Severity, range: TextRange, source: &str) -> Self {
+        let (line, column) = line_column(source, range.start());
+        Self {
+            kind,
+            severity,
+            range,
+            line,
+            column,
+        }
+    }
+
+    /// Render the diagnostic, showing the offending source line with a caret.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let text = "breezy (3.3.4-1) unstable; urgency=\n";
+    /// let Err(err) = text.parse::<debian_changelog::ChangeLog>() else {
+    ///     panic!("expected a parse error");
+    /// };
+    /// assert_eq!(
+    ///     err.diagnostics()[0].render(text),
+    ///     r#"error: expected metadata value
+    ///  --> line 1, column 36
+    ///   |
+    /// 1 | breezy (3.3.4-1) unstable; urgency=
+    ///   |                                    ^
+    /// "#
+    /// );
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let line_start = source
+            .split_inclusive('\n')
+            .take(self.line - 1)
+            .map(|l| l.len())
+            .sum::<usize>();
+        let text = source[line_start..]
+            .split('\n')
+            .next()
+            .unwrap_or("")
+            .trim_end_matches('\r');
+
+        let range_start = usize::from(self.range.start()).max(line_start);
+        let range_end = usize::from(self.range.end()).min(line_start + text.len());
+        let width = if range_end > range_start {
+            source[range_start..range_end].chars().count()
+        } else {
+            1
+        };
+        // Keep tabs, so that the caret lines up with the source line
+        let padding = text
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        let lineno = self.line.to_string();
+        let gutter = " ".repeat(lineno.len());
+        format!(
+            "{}: {}\n{}--> line {}, column {}\n{} |\n{} | {}\n{} | {}{}\n",
+            self.severity,
+            self.kind,
+            gutter,
+            self.line,
+            self.column,
+            gutter,
+            lineno,
+            text,
+            gutter,
+            padding,
+            "^".repeat(width)
+        )
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.severity, self.kind
+        )
+    }
+}
+
+/// Return the line and column (both counting from 1) of an offset in the source text.
+pub(crate) fn line_column(source: &str, offset: TextSize) -> (usize, usize) {
+    let start = usize::from(offset).min(source.len());
+    let before = &source[..start];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+/// Convert a byte offset and length into a `TextRange`.
+pub(crate) fn text_range(offset: usize, len: usize) -> TextRange {
+    TextRange::at(TextSize::from(offset as u32), TextSize::from(len as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_column() {
+        let source = "first line\nsecond línea\n";
+        let d = Diagnostic::new(
+            DiagnosticKind::ExpectedDetail,
+            Severity::Error,
+            text_range(19, 2),
+            source,
+        );
+        assert_eq!((d.line, d.column), (2, 9));
+        assert_eq!(d.to_string(), "2:9: error: expected detail");
+    }
+
+    #[test]
+    fn test_render() {
+        let source = "first line\nsecond line\n";
+        let d = Diagnostic::new(
+            DiagnosticKind::UnexpectedToken(Some(SyntaxKind::TEXT)),
+            Severity::Error,
+            text_range(18, 4),
+            source,
+        );
+        assert_eq!(
+            d.render(source),
+            r#"error: unexpected TEXT
+ --> line 2, column 8
+  |
+2 | second line
+  |        ^^^^
+"#
+        );
+    }
+
+    #[test]
+    fn test_render_end_of_file() {
+        let source = "no newline";
+        let d = Diagnostic::new(
+            DiagnosticKind::UnexpectedEndOfFile,
+            Severity::Error,
+            text_range(10, 0),
+            source,
+        );
+        assert_eq!((d.line, d.column), (1, 11));
+        assert_eq!(
+            d.render(source),
+            r#"error: unexpected end of file
+ --> line 1, column 11
+  |
+1 | no newline
+  |           ^
+"#
+        );
+    }
+}
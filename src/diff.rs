@@ -0,0 +1,427 @@
+//! Semantic differences between two revisions of a changelog, at the level of entries.
+//!
+//! Entries are matched up by version, and repeated versions in the order in which they appear.
+//! An entry whose version changed is matched with the entry in the same position, if that
+//! one's version also has no match. Entries without a parseable version are ignored.
+//! With the `serde` feature enabled, the diff can be serialized, e.g. to JSON.
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::ChangeLog;
+//! use debian_changelog::diff::{EntryDiff, Field, FieldChange};
+//!
+//! let old: ChangeLog = r#"foo (1.0-1) UNRELEASED; urgency=low
+//!
+//!   * Initial release.
+//!
+//!  -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+//! "#.parse().unwrap();
+//! let new: ChangeLog = old.to_string().replace("UNRELEASED", "unstable").parse().unwrap();
+//!
+//! let diff = old.diff(&new);
+//! assert_eq!(
+//!     diff.entries,
+//!     vec![EntryDiff::Modified {
+//!         version: "1.0-1".to_string(),
+//!         fields: vec![FieldChange {
+//!             field: Field::Distribution,
+//!             old: Some("UNRELEASED".to_string()),
+//!             new: Some("unstable".to_string()),
+//!         }],
+//!         added_lines: vec![],
+//!         removed_lines: vec![],
+//!     }]
+//! );
+//! ```
+
+use crate::{ChangeLog, Entry};
+use debversion::Version;
+
+/// A field of an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Field {
+    Version,
+    Distribution,
+    Urgency,
+    Maintainer,
+    Date,
+}
+
+impl Field {
+    /// All fields, in the order in which they are compared.
+    pub const ALL: &'static [Field] = &[
+        Field::Version,
+        Field::Distribution,
+        Field::Urgency,
+        Field::Maintainer,
+        Field::Date,
+    ];
+
+    /// Return the value of the field for an entry, as written.
+    pub fn value(&self, entry: &Entry) -> Option<String> {
+        match self {
+            Field::Version => entry.version().map(|v| v.to_string()),
+            Field::Distribution => entry.distributions().map(|d| d.join(" ")),
+            Field::Urgency => entry.urgency_value().map(|u| u.to_string()),
+            Field::Maintainer => match (entry.maintainer(), entry.email()) {
+                (Some(name), Some(email)) => Some(format!("{} <{}>", name, email)),
+                (name, email) => name.or(email),
+            },
+            Field::Date => entry.timestamp(),
+        }
+    }
+}
+
+/// A change to a field of an entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldChange {
+    pub field: Field,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A difference in a single entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "lowercase"))]
+pub enum EntryDiff {
+    /// The entry only exists in the new changelog.
+    Added {
+        version: String,
+        change_lines: Vec<String>,
+    },
+    /// The entry only exists in the old changelog.
+    Removed {
+        version: String,
+        change_lines: Vec<String>,
+    },
+    /// The entry exists in both changelogs, but differs.
+    Modified {
+        version: String,
+        fields: Vec<FieldChange>,
+        added_lines: Vec<String>,
+        removed_lines: Vec<String>,
+    },
+}
+
+impl EntryDiff {
+    /// Returns the version of the entry.
+    pub fn version(&self) -> &str {
+        match self {
+            EntryDiff::Added { version, .. }
+            | EntryDiff::Removed { version, .. }
+            | EntryDiff::Modified { version, .. } => version,
+        }
+    }
+}
+
+/// The differences between two changelogs, newest entries first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangeLogDiff {
+    pub entries: Vec<EntryDiff>,
+}
+
+impl ChangeLogDiff {
+    /// Return whether there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Return the positions of the items of a longest common subsequence of `a` and `b`, as
+/// (index in `a`, index in `b`) pairs in order.
+pub(crate) fn common_subsequence<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// A version, and how many entries above it in the same changelog have the same version.
+pub(crate) type Key = (Version, usize);
+
+/// Return the key for the next entry with a version, given the keys of the entries above it.
+pub(crate) fn occurrence<'a>(above: impl Iterator<Item = &'a Key>, version: Version) -> Key {
+    let index = above.filter(|(v, _)| *v == version).count();
+    (version, index)
+}
+
+/// Return the lines in `a` that are not in `b`, taking duplicates into account.
+fn subtract(a: &[String], b: &[String]) -> Vec<String> {
+    let mut remaining = b.to_vec();
+    a.iter()
+        .filter(|line| match remaining.iter().position(|l| l == *line) {
+            Some(pos) => {
+                remaining.remove(pos);
+                false
+            }
+            None => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Compare two revisions of an entry.
+fn diff_entry(old: &Entry, new: &Entry) -> Option<EntryDiff> {
+    let fields = Field::ALL
+        .iter()
+        .filter_map(|field| {
+            let (old, new) = (field.value(old), field.value(new));
+            (old != new).then_some(FieldChange {
+                field: *field,
+                old,
+                new,
+            })
+        })
+        .collect::<Vec<_>>();
+    let old_lines = old.change_lines().collect::<Vec<_>>();
+    let new_lines = new.change_lines().collect::<Vec<_>>();
+    let added_lines = subtract(&new_lines, &old_lines);
+    let removed_lines = subtract(&old_lines, &new_lines);
+    if fields.is_empty() && added_lines.is_empty() && removed_lines.is_empty() {
+        return None;
+    }
+    Some(EntryDiff::Modified {
+        version: new.version()?.to_string(),
+        fields,
+        added_lines,
+        removed_lines,
+    })
+}
+
+impl ChangeLog {
+    /// Compare this changelog to a newer revision of it.
+    pub fn diff(&self, new: &ChangeLog) -> ChangeLogDiff {
+        let keyed = |changelog: &ChangeLog| {
+            let mut ret: Vec<(Key, Entry)> = vec![];
+            for entry in changelog.entries() {
+                if let Some(version) = entry.version() {
+                    let key = occurrence(ret.iter().map(|(k, _)| k), version);
+                    ret.push((key, entry));
+                }
+            }
+            ret
+        };
+        fn find<'a>(entries: &'a [(Key, Entry)], key: &Key) -> Option<&'a Entry> {
+            entries.iter().find(|(k, _)| k == key).map(|(_, e)| e)
+        }
+        let unmatched = |entries: &[(Key, Entry)], others: &[(Key, Entry)]| {
+            entries
+                .iter()
+                .map(|(k, _)| find(others, k).is_none())
+                .collect::<Vec<_>>()
+        };
+        let (old_entries, new_entries) = (keyed(self), keyed(new));
+        let old_unmatched = unmatched(&old_entries, &new_entries);
+        let new_unmatched = unmatched(&new_entries, &old_entries);
+        // Neither entry at this position has a match, so the version was changed
+        let renamed =
+            |i: usize| old_unmatched.get(i) == Some(&true) && new_unmatched.get(i) == Some(&true);
+
+        let mut entries = vec![];
+        for (i, (key, entry)) in new_entries.iter().enumerate() {
+            let old = if renamed(i) {
+                Some(&old_entries[i].1)
+            } else {
+                find(&old_entries, key)
+            };
+            match old {
+                Some(old) => entries.extend(diff_entry(old, entry).map(|d| (key, d))),
+                None => entries.push((
+                    key,
+                    EntryDiff::Added {
+                        version: key.0.to_string(),
+                        change_lines: entry.change_lines().collect(),
+                    },
+                )),
+            }
+        }
+        for (i, (key, entry)) in old_entries.iter().enumerate() {
+            if old_unmatched[i] && !renamed(i) {
+                entries.push((
+                    key,
+                    EntryDiff::Removed {
+                        version: key.0.to_string(),
+                        change_lines: entry.change_lines().collect(),
+                    },
+                ));
+            }
+        }
+        // Newest version first; repeated versions in the order in which they appear
+        entries.sort_by(|((a, i), _), ((b, j), _)| b.cmp(a).then(i.cmp(j)));
+        ChangeLogDiff {
+            entries: entries.into_iter().map(|(_, d)| d).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = r#"foo (1.0-2) UNRELEASED; urgency=low
+
+  * Fix a bug.
+  * Fix another bug.
+
+ -- Joe Example <joe@example.com>  Tue, 05 Sep 2023 18:13:45 -0500
+
+foo (1.0-1) unstable; urgency=low
+
+  * Initial release.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+
+    #[test]
+    fn test_identical() {
+        let changelog: ChangeLog = OLD.parse().unwrap();
+        assert!(changelog.diff(&changelog).is_empty());
+    }
+
+    #[test]
+    fn test_added_removed() {
+        let old: ChangeLog = OLD.parse().unwrap();
+        let new: ChangeLog = format!("{}\n", OLD.split("\n\nfoo (1.0-1)").next().unwrap())
+            .parse()
+            .unwrap();
+        assert_eq!(
+            old.diff(&new).entries,
+            vec![EntryDiff::Removed {
+                version: "1.0-1".to_string(),
+                change_lines: vec!["* Initial release.".to_string()],
+            }]
+        );
+        assert_eq!(
+            new.diff(&old).entries,
+            vec![EntryDiff::Added {
+                version: "1.0-1".to_string(),
+                change_lines: vec!["* Initial release.".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_modified() {
+        let old: ChangeLog = OLD.parse().unwrap();
+        let new: ChangeLog = OLD
+            .replacen("UNRELEASED; urgency=low", "unstable; urgency=high", 1)
+            .replace(
+                "Joe Example <joe@example.com>  Tue",
+                "Jane Example <jane@example.com>  Wed",
+            )
+            .replace("Fix a bug.", "Fix a crash.")
+            .parse()
+            .unwrap();
+        let diff = old.diff(&new);
+        assert_eq!(diff.entries.len(), 1);
+        assert_eq!(diff.entries[0].version(), "1.0-2");
+        let EntryDiff::Modified {
+            fields,
+            added_lines,
+            removed_lines,
+            ..
+        } = &diff.entries[0]
+        else {
+            panic!("expected a modified entry");
+        };
+        assert_eq!(
+            fields.iter().map(|f| f.field).collect::<Vec<_>>(),
+            vec![
+                Field::Distribution,
+                Field::Urgency,
+                Field::Maintainer,
+                Field::Date
+            ]
+        );
+        assert_eq!(
+            fields[2].new.as_deref(),
+            Some("Jane Example <jane@example.com>")
+        );
+        assert_eq!(added_lines, &vec!["* Fix a crash.".to_string()]);
+        assert_eq!(removed_lines, &vec!["* Fix a bug.".to_string()]);
+    }
+
+    #[test]
+    fn test_version_changed() {
+        let old: ChangeLog = OLD.parse().unwrap();
+        let new: ChangeLog = OLD.replacen("1.0-2", "1.0-3", 1).parse().unwrap();
+        assert_eq!(
+            old.diff(&new).entries,
+            vec![EntryDiff::Modified {
+                version: "1.0-3".to_string(),
+                fields: vec![FieldChange {
+                    field: Field::Version,
+                    old: Some("1.0-2".to_string()),
+                    new: Some("1.0-3".to_string()),
+                }],
+                added_lines: vec![],
+                removed_lines: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_repeated_version() {
+        let old: ChangeLog = OLD.replacen("1.0-2", "1.0-1", 1).parse().unwrap();
+        assert!(old.diff(&old).is_empty());
+        let new: ChangeLog = old
+            .to_string()
+            .replace("Initial release.", "First release.")
+            .parse()
+            .unwrap();
+        assert_eq!(
+            old.diff(&new).entries,
+            vec![EntryDiff::Modified {
+                version: "1.0-1".to_string(),
+                fields: vec![],
+                added_lines: vec!["* First release.".to_string()],
+                removed_lines: vec!["* Initial release.".to_string()],
+            }]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json() {
+        let old: ChangeLog = OLD.parse().unwrap();
+        let new: ChangeLog = OLD.replacen("UNRELEASED", "unstable", 1).parse().unwrap();
+        assert_eq!(
+            serde_json::to_value(old.diff(&new)).unwrap(),
+            serde_json::json!({
+                "entries": [{
+                    "kind": "modified",
+                    "version": "1.0-2",
+                    "fields": [{"field": "distribution", "old": "UNRELEASED", "new": "unstable"}],
+                    "added_lines": [],
+                    "removed_lines": [],
+                }]
+            })
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! Knowledge about Debian and Ubuntu distributions.
+//!
+//! The list of releases is read from the CSV files shipped by the distro-info-data
+//! package, falling back to a built-in copy if they are not available.
+//!
+//! The registry is used by the linter ([`crate::lint::Check::UnknownDistribution`]) and by
+//! [`crate::version::VersionBump::for_distribution`]. [`crate::release`] and the entry
+//! builder do not check distributions against it; callers that want to reject unknown
+//! targets should check them with [`DistributionRegistry::is_known`] first.
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::distributions::{DistributionRegistry, Pocket, Vendor};
+//!
+//! let registry = DistributionRegistry::builtin();
+//! let target = registry.lookup("bookworm-backports").unwrap();
+//! assert_eq!(target.vendor, Vendor::Debian);
+//! assert_eq!(target.series, "bookworm");
+//! assert_eq!(target.pocket, Some(Pocket::Backports));
+//! assert!(registry.is_known("unstable"));
+//! assert!(!registry.is_known("bookwrom"));
+//! ```
+
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+
+/// The directory that distro-info-data installs its CSV files in.
+pub const DISTRO_INFO_DIR: &str = "/usr/share/distro-info";
+
+const BUILTIN_DEBIAN: &str = include_str!("distro-info/debian.csv");
+const BUILTIN_UBUNTU: &str = include_str!("distro-info/ubuntu.csv");
+
+/// A distribution vendor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Vendor {
+    Debian,
+    Ubuntu,
+}
+
+impl Vendor {
+    /// The name of the CSV file for this vendor in the distro-info directory.
+    fn csv_name(&self) -> &'static str {
+        match self {
+            Vendor::Debian => "debian.csv",
+            Vendor::Ubuntu => "ubuntu.csv",
+        }
+    }
+
+    /// The pockets that can follow a series name for this vendor, e.g. "bookworm-security".
+    pub fn pockets(&self) -> &'static [Pocket] {
+        match self {
+            Vendor::Debian => &[
+                Pocket::Security,
+                Pocket::BackportsSloppy,
+                Pocket::Backports,
+                Pocket::ProposedUpdates,
+                Pocket::Updates,
+            ],
+            Vendor::Ubuntu => &[
+                Pocket::Security,
+                Pocket::Updates,
+                Pocket::Proposed,
+                Pocket::Backports,
+            ],
+        }
+    }
+}
+
+/// A pocket (or suite suffix) of a release, e.g. "-security".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Pocket {
+    Security,
+    Updates,
+    ProposedUpdates,
+    Proposed,
+    Backports,
+    BackportsSloppy,
+}
+
+impl Pocket {
+    /// The suffix that is appended to the series name.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Pocket::Security => "-security",
+            Pocket::Updates => "-updates",
+            Pocket::ProposedUpdates => "-proposed-updates",
+            Pocket::Proposed => "-proposed",
+            Pocket::Backports => "-backports",
+            Pocket::BackportsSloppy => "-backports-sloppy",
+        }
+    }
+}
+
+/// A single release, as listed in distro-info-data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Release {
+    pub vendor: Vendor,
+    /// The version number, e.g. "12" or "24.04 LTS". Empty for sid and experimental.
+    pub version: String,
+    /// The full codename, e.g. "Bookworm" or "Noble Numbat".
+    pub codename: String,
+    /// The name used in changelogs, e.g. "bookworm" or "noble".
+    pub series: String,
+    pub created: Option<NaiveDate>,
+    pub release: Option<NaiveDate>,
+    pub eol: Option<NaiveDate>,
+}
+
+impl Release {
+    /// The major version number, as used in version suffixes like "~bpo12+1".
+    pub fn major_version(&self) -> Option<u32> {
+        self.version.split(['.', ' ']).next()?.parse().ok()
+    }
+
+    /// Return whether the release had been released on the given date.
+    pub fn is_released(&self, date: NaiveDate) -> bool {
+        self.release.is_some_and(|r| r <= date)
+    }
+
+    /// Return whether the release existed, but had not been released yet, on the given date.
+    pub fn is_in_development(&self, date: NaiveDate) -> bool {
+        self.created.is_some_and(|c| c <= date) && !self.is_released(date)
+    }
+}
+
+/// A distribution as written in a changelog, resolved to a release.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Target {
+    pub vendor: Vendor,
+    /// The series of the release, e.g. "bookworm" for "stable-security".
+    pub series: String,
+    pub pocket: Option<Pocket>,
+}
+
+/// Parse a CSV file from distro-info-data.
+fn parse_csv(vendor: Vendor, text: &str) -> Result<Vec<Release>, String> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header = lines
+        .next()
+        .ok_or_else(|| "missing header".to_string())?
+        .split(',')
+        .collect::<Vec<_>>();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|c| *c == name)
+            .ok_or_else(|| format!("missing column: {}", name))
+    };
+    let (version, codename, series) = (column("version")?, column("codename")?, column("series")?);
+    let (created, release, eol) = (column("created")?, column("release")?, column("eol")?);
+
+    let mut ret = vec![];
+    for line in lines {
+        let fields = line.split(',').collect::<Vec<_>>();
+        let field = |i: usize| fields.get(i).copied().unwrap_or("");
+        let date = |i: usize| {
+            let value = field(i);
+            if value.is_empty() {
+                Ok(None)
+            } else {
+                NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map(Some)
+                    .map_err(|e| format!("invalid date {}: {}", value, e))
+            }
+        };
+        if field(series).is_empty() {
+            return Err(format!("missing series: {}", line));
+        }
+        ret.push(Release {
+            vendor,
+            version: field(version).to_string(),
+            codename: field(codename).to_string(),
+            series: field(series).to_string(),
+            created: date(created)?,
+            release: date(release)?,
+            eol: date(eol)?,
+        });
+    }
+    Ok(ret)
+}
+
+/// Suite names that were used in the past, but that distro-info does not know about.
+const HISTORICAL_SUITES: &[&str] = &["frozen", "proposed-updates"];
+
+/// A registry of known releases, in release order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistributionRegistry {
+    releases: Vec<Release>,
+}
+
+impl Default for DistributionRegistry {
+    /// Load the registry from the system, falling back to the built-in data.
+    fn default() -> Self {
+        Self::load()
+    }
+}
+
+impl DistributionRegistry {
+    /// Load the registry from [`DISTRO_INFO_DIR`], falling back to the built-in data.
+    pub fn load() -> Self {
+        Self::from_dir(DISTRO_INFO_DIR).unwrap_or_else(|_| Self::builtin())
+    }
+
+    /// The registry loaded from the system with [`DistributionRegistry::load`], which is only
+    /// loaded once.
+    pub fn system() -> &'static Self {
+        static SYSTEM: std::sync::OnceLock<DistributionRegistry> = std::sync::OnceLock::new();
+        SYSTEM.get_or_init(Self::load)
+    }
+
+    /// Load the registry from a directory with distro-info CSV files.
+    pub fn from_dir(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let mut releases = vec![];
+        for vendor in [Vendor::Debian, Vendor::Ubuntu] {
+            let text = std::fs::read_to_string(path.as_ref().join(vendor.csv_name()))?;
+            releases.extend(
+                parse_csv(vendor, text.as_str())
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            );
+        }
+        Ok(Self { releases })
+    }
+
+    /// The registry with the data that was built into this crate.
+    pub fn builtin() -> Self {
+        let mut releases = parse_csv(Vendor::Debian, BUILTIN_DEBIAN).unwrap();
+        releases.extend(parse_csv(Vendor::Ubuntu, BUILTIN_UBUNTU).unwrap());
+        Self { releases }
+    }
+
+    /// Returns all known releases, in release order per vendor.
+    pub fn releases(&self) -> impl Iterator<Item = &Release> + '_ {
+        self.releases.iter()
+    }
+
+    /// Look up a release by its series name, e.g. "bookworm".
+    pub fn release(&self, series: &str) -> Option<&Release> {
+        self.releases.iter().find(|r| r.series == series)
+    }
+
+    /// Debian releases with a version number, i.e. excluding sid and experimental.
+    fn numbered_debian_releases(&self) -> impl Iterator<Item = &Release> + '_ {
+        self.releases
+            .iter()
+            .filter(|r| r.vendor == Vendor::Debian && !r.version.is_empty())
+    }
+
+    /// Resolve a suite name like "stable" or "devel" to a series name, at the given date.
+    pub fn series_for_suite(&self, suite: &str, date: NaiveDate) -> Option<&str> {
+        let released = || {
+            self.numbered_debian_releases()
+                .filter(|r| r.is_released(date))
+                .collect::<Vec<_>>()
+        };
+        let release = match suite {
+            "unstable" => self.release("sid"),
+            "rc-buggy" => self.release("experimental"),
+            "testing" => self
+                .numbered_debian_releases()
+                .find(|r| r.is_in_development(date)),
+            "stable" => released().last().copied(),
+            "oldstable" => released().iter().rev().nth(1).copied(),
+            "oldoldstable" => released().iter().rev().nth(2).copied(),
+            "devel" => self
+                .releases
+                .iter()
+                .rev()
+                .find(|r| r.vendor == Vendor::Ubuntu && r.is_in_development(date)),
+            _ => None,
+        };
+        release.map(|r| r.series.as_str())
+    }
+
+    /// Return the suite name of a Debian series at the given date, e.g. "stable" for "bookworm".
+    pub fn suite_for_series(&self, series: &str, date: NaiveDate) -> Option<&'static str> {
+        [
+            "unstable",
+            "rc-buggy",
+            "testing",
+            "stable",
+            "oldstable",
+            "oldoldstable",
+        ]
+        .into_iter()
+        .find(|suite| self.series_for_suite(suite, date) == Some(series))
+        .map(|suite| {
+            if suite == "rc-buggy" {
+                "experimental"
+            } else {
+                suite
+            }
+        })
+    }
+
+    /// Resolve a distribution as written in a changelog, at the given date.
+    pub fn lookup_at(&self, name: &str, date: NaiveDate) -> Option<Target> {
+        let resolve = |base: &str| {
+            self.release(base)
+                .map(|r| r.series.as_str())
+                .or_else(|| self.series_for_suite(base, date))
+                .and_then(|series| self.release(series))
+        };
+        if let Some(release) = resolve(name) {
+            return Some(Target {
+                vendor: release.vendor,
+                series: release.series.clone(),
+                pocket: None,
+            });
+        }
+        for vendor in [Vendor::Debian, Vendor::Ubuntu] {
+            for pocket in vendor.pockets() {
+                let Some(base) = name.strip_suffix(pocket.suffix()) else {
+                    continue;
+                };
+                if let Some(release) = resolve(base).filter(|r| r.vendor == vendor) {
+                    return Some(Target {
+                        vendor,
+                        series: release.series.clone(),
+                        pocket: Some(*pocket),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Resolve a distribution as written in a changelog, e.g. "bookworm-security" or "unstable".
+    ///
+    /// Suite aliases are resolved at the date of [`crate::reproducible::now`].
+    pub fn lookup(&self, name: &str) -> Option<Target> {
+        self.lookup_at(name, crate::reproducible::now().date_naive())
+    }
+
+    /// Return whether a distribution is known at the given date.
+    ///
+    /// Historical suite names that appear in old changelogs, like "frozen", are accepted too.
+    pub fn is_known_at(&self, name: &str, date: NaiveDate) -> bool {
+        HISTORICAL_SUITES.contains(&name) || self.lookup_at(name, date).is_some()
+    }
+
+    /// Return whether a distribution is known.
+    pub fn is_known(&self, name: &str) -> bool {
+        self.is_known_at(name, crate::reproducible::now().date_naive())
+    }
+
+    /// Compare the release order of two series of the same vendor.
+    pub fn compare(&self, a: &str, b: &str) -> Option<Ordering> {
+        let position = |series: &str| self.releases.iter().position(|r| r.series == series);
+        let (a, b) = (position(a)?, position(b)?);
+        if self.releases[a].vendor != self.releases[b].vendor {
+            return None;
+        }
+        Some(a.cmp(&b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let releases = parse_csv(
+            Vendor::Debian,
+            "version,codename,series,created,release,eol\n12,Bookworm,bookworm,2021-08-14,2023-06-10\n,Sid,sid,1993-08-16\n",
+        )
+        .unwrap();
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].major_version(), Some(12));
+        assert_eq!(releases[0].release, Some(date("2023-06-10")));
+        assert_eq!(releases[0].eol, None);
+        assert_eq!(releases[1].major_version(), None);
+        assert!(parse_csv(Vendor::Debian, "version,codename\n").is_err());
+    }
+
+    #[test]
+    fn test_suites() {
+        let registry = DistributionRegistry::builtin();
+        let d = date("2024-01-01");
+        assert_eq!(registry.series_for_suite("stable", d), Some("bookworm"));
+        assert_eq!(registry.series_for_suite("oldstable", d), Some("bullseye"));
+        assert_eq!(registry.series_for_suite("testing", d), Some("trixie"));
+        assert_eq!(registry.series_for_suite("unstable", d), Some("sid"));
+        assert_eq!(registry.series_for_suite("devel", d), Some("noble"));
+        assert_eq!(registry.suite_for_series("bookworm", d), Some("stable"));
+        assert_eq!(registry.suite_for_series("sid", d), Some("unstable"));
+        assert_eq!(registry.suite_for_series("noble", d), None);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let registry = DistributionRegistry::builtin();
+        let d = date("2024-01-01");
+        assert_eq!(
+            registry.lookup_at("stable-security", d),
+            Some(Target {
+                vendor: Vendor::Debian,
+                series: "bookworm".to_string(),
+                pocket: Some(Pocket::Security),
+            })
+        );
+        assert_eq!(
+            registry
+                .lookup_at("bullseye-backports-sloppy", d)
+                .unwrap()
+                .pocket,
+            Some(Pocket::BackportsSloppy)
+        );
+        assert_eq!(
+            registry.lookup_at("jammy-proposed", d),
+            Some(Target {
+                vendor: Vendor::Ubuntu,
+                series: "jammy".to_string(),
+                pocket: Some(Pocket::Proposed),
+            })
+        );
+        assert_eq!(
+            registry.lookup_at("experimental", d).unwrap().series,
+            "experimental"
+        );
+        assert_eq!(registry.lookup_at("jammy-proposed-updates", d), None);
+        assert_eq!(registry.lookup_at("UNRELEASED", d), None);
+    }
+
+    #[test]
+    fn test_compare() {
+        let registry = DistributionRegistry::builtin();
+        assert_eq!(registry.compare("buster", "bookworm"), Some(Ordering::Less));
+        assert_eq!(registry.compare("noble", "jammy"), Some(Ordering::Greater));
+        assert_eq!(registry.compare("noble", "bookworm"), None);
+    }
+
+    #[test]
+    fn test_from_dir() {
+        assert!(DistributionRegistry::from_dir("/nonexistent").is_err());
+        let registry = DistributionRegistry::from_dir(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/distro-info"),
+        )
+        .unwrap();
+        assert_eq!(registry, DistributionRegistry::builtin());
+    }
+}
@@ -0,0 +1,384 @@
+//! Canonical formatting of changelogs, similar to what rustfmt does for Rust code.
+//!
+//! The canonical layout is:
+//!
+//! * a header of the form `package (version) distribution; key=value, key=value`
+//! * a single blank line before and after the changes
+//! * changes indented by two spaces, with bullet points rewrapped to fit
+//! * a footer of the form ` -- Name <email>  date`
+//! * a single blank line between entries
+//!
+//! Comments, unknown metadata and any entries that could not be parsed are
+//! kept as they are.
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::ChangeLog;
+//!
+//! let mut changelog: ChangeLog = r#"breezy (3.3.4-1)   unstable;urgency=low
+//!   * New upstream release.
+//!
+//!
+//!  -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+//! "#.parse().unwrap();
+//! assert!(!changelog.is_formatted());
+//! assert!(changelog.format().unwrap());
+//! assert_eq!(changelog.to_string(), r#"breezy (3.3.4-1) unstable; urgency=low
+//!
+//!   * New upstream release.
+//!
+//!  -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+//! "#);
+//! assert!(changelog.is_formatted());
+//! ```
+
+use crate::parse::SyntaxNode;
+use crate::{ChangeLog, Error, SyntaxKind};
+use rowan::ast::AstNode;
+use rowan::NodeOrToken;
+
+/// Return the text of the direct child tokens of a node with the given kind.
+fn child_tokens(node: &SyntaxNode, kind: SyntaxKind) -> Vec<String> {
+    node.children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|t| t.kind() == kind)
+        .map(|t| t.text().to_string())
+        .collect()
+}
+
+fn child(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxNode> {
+    node.children().find(|n| n.kind() == kind)
+}
+
+/// Return whether a node only has direct children of the given kinds, and no errors.
+fn only_children(node: &SyntaxNode, kinds: &[SyntaxKind]) -> bool {
+    node.children_with_tokens()
+        .all(|it| kinds.contains(&it.kind()))
+        && !node.descendants().any(|n| n.kind() == SyntaxKind::ERROR)
+}
+
+/// Format a header, or return None if it can not be formatted without losing anything.
+fn format_header(header: &SyntaxNode) -> Option<String> {
+    use SyntaxKind::*;
+    if !only_children(
+        header,
+        &[
+            IDENTIFIER,
+            WHITESPACE,
+            VERSION,
+            DISTRIBUTIONS,
+            METADATA,
+            NEWLINE,
+        ],
+    ) {
+        return None;
+    }
+    let package = child_tokens(header, IDENTIFIER).into_iter().next()?;
+    let version = child_tokens(header, VERSION).into_iter().next()?;
+    let mut line = format!("{} {}", package, version);
+
+    if let Some(distributions) = child(header, DISTRIBUTIONS) {
+        for distribution in child_tokens(&distributions, IDENTIFIER) {
+            line.push(' ');
+            line.push_str(distribution.as_str());
+        }
+    }
+
+    if let Some(metadata) = child(header, METADATA) {
+        let mut entries = vec![];
+        for entry in metadata.children().filter(|n| n.kind() == METADATA_ENTRY) {
+            let key = child(&entry, METADATA_KEY)?.text().to_string();
+            let value = child(&entry, METADATA_VALUE)?
+                .children_with_tokens()
+                .filter(|it| it.kind() != WHITESPACE)
+                .map(|it| it.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            entries.push(format!("{}={}", key, value));
+        }
+        if !entries.is_empty() {
+            line.push_str("; ");
+            line.push_str(entries.join(", ").as_str());
+        }
+    }
+    Some(line)
+}
+
+/// Format a footer, or return None if it can not be formatted without losing anything.
+fn format_footer(footer: &SyntaxNode) -> Option<String> {
+    use SyntaxKind::*;
+    let separator_ok = footer
+        .children()
+        .all(|n| n.kind() != ERROR || n.children_with_tokens().all(|it| it.kind() == WHITESPACE));
+    if !separator_ok
+        || !footer.children_with_tokens().all(|it| {
+            [
+                INDENT, MAINTAINER, WHITESPACE, EMAIL, ERROR, TIMESTAMP, NEWLINE,
+            ]
+            .contains(&it.kind())
+        })
+    {
+        return None;
+    }
+    let maintainer = child(footer, MAINTAINER)?.text().to_string();
+    let email = child_tokens(footer, EMAIL).into_iter().next()?;
+    let timestamp = child(footer, TIMESTAMP)?
+        .text()
+        .to_string()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(format!(
+        " -- {} {}  {}",
+        maintainer.trim(),
+        email,
+        timestamp
+    ))
+}
+
+/// Rewrap the bulleted changes in a list of lines, leaving other lines alone.
+fn rewrap_body(lines: Vec<String>) -> Vec<String> {
+    let mut ret = vec![];
+    let mut change: Vec<String> = vec![];
+    let mut indent = String::new();
+    let flush = |change: &mut Vec<String>, ret: &mut Vec<String>| {
+        ret.extend(
+            crate::textwrap::rewrap_changes(change.iter().map(|l| l.as_str()))
+                .map(|l| l.to_string()),
+        );
+        change.clear();
+    };
+    for line in lines {
+        if let Some(bullet) = lazy_regex::regex_find!(r"^\s*[\+\-\*] ", line.as_str()) {
+            flush(&mut change, &mut ret);
+            indent = " ".repeat(bullet.len());
+            change.push(line);
+        } else if !change.is_empty() && !line.is_empty() && line.starts_with(indent.as_str()) {
+            change.push(line);
+        } else {
+            flush(&mut change, &mut ret);
+            ret.push(line);
+        }
+    }
+    flush(&mut change, &mut ret);
+    ret
+}
+
+/// Format an entry, or return None if it can not be formatted without losing anything.
+fn format_entry(entry: &SyntaxNode) -> Option<String> {
+    use SyntaxKind::*;
+    let mut header = None;
+    let mut footer = None;
+    let mut lines = vec![];
+    for child in entry.children_with_tokens() {
+        match (child.kind(), child) {
+            (ENTRY_HEADER, NodeOrToken::Node(n)) if header.is_none() && lines.is_empty() => {
+                header = Some(format_header(&n)?);
+            }
+            (ENTRY_FOOTER, NodeOrToken::Node(n)) if header.is_some() && footer.is_none() => {
+                footer = Some(format_footer(&n)?);
+            }
+            (ENTRY_BODY, NodeOrToken::Node(n)) if header.is_some() && footer.is_none() => {
+                if !only_children(&n, &[INDENT, DETAIL, NEWLINE]) {
+                    return None;
+                }
+                let detail = child_tokens(&n, DETAIL).concat();
+                lines.push(detail.trim_end().to_string());
+            }
+            (EMPTY_LINE, _) if header.is_some() => {
+                if footer.is_none() {
+                    lines.push(String::new());
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    // Collapse runs of blank lines, and drop them at the start and end
+    lines.dedup_by(|a, b| a.is_empty() && b.is_empty());
+    while lines.first().is_some_and(|l| l.is_empty()) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+
+    let mut ret = header?;
+    ret.push_str("\n\n");
+    for line in rewrap_body(lines) {
+        if !line.is_empty() {
+            ret.push_str("  ");
+            ret.push_str(line.as_str());
+        }
+        ret.push('\n');
+    }
+    if !ret.ends_with("\n\n") {
+        ret.push('\n');
+    }
+    ret.push_str(footer?.as_str());
+    ret.push('\n');
+    Some(ret)
+}
+
+impl ChangeLog {
+    /// Return the text of the changelog in canonical layout.
+    fn formatted(&self) -> String {
+        let mut ret = String::new();
+        // Whether the last item was an entry, and the number of blank lines since
+        let mut previous: Option<bool> = None;
+        let mut blank_lines = 0;
+        for child in self.syntax().children_with_tokens() {
+            if child.kind() == SyntaxKind::EMPTY_LINE {
+                blank_lines += 1;
+                continue;
+            }
+            let is_entry = child.kind() == SyntaxKind::ENTRY;
+            let formatted = child.as_node().filter(|_| is_entry).and_then(format_entry);
+            if let Some(previous) = previous {
+                if previous && is_entry {
+                    blank_lines = 1;
+                } else if previous || is_entry {
+                    blank_lines = blank_lines.min(1);
+                }
+                ret.push_str("\n".repeat(blank_lines).as_str());
+            }
+            ret.push_str(formatted.unwrap_or_else(|| child.to_string()).as_str());
+            previous = Some(is_entry);
+            blank_lines = 0;
+        }
+        ret
+    }
+
+    /// Return whether the changelog is already in canonical layout.
+    pub fn is_formatted(&self) -> bool {
+        self.formatted() == self.to_string()
+    }
+
+    /// Rewrite the changelog into canonical layout.
+    ///
+    /// Returns whether anything was changed. The contents of the changelog are replaced as a
+    /// whole, so if anything was changed, entries, headers and other nodes obtained from the
+    /// changelog before the call are detached from it: get them again afterwards.
+    pub fn format(&mut self) -> Result<bool, Error> {
+        let formatted = self.formatted();
+        if formatted == self.to_string() {
+            return Ok(false);
+        }
+        let (new, _) = ChangeLog::read_relaxed(formatted.as_bytes())?;
+        let children = new.syntax().children_with_tokens().collect::<Vec<_>>();
+        for child in children.iter() {
+            child.detach();
+        }
+        let count = self.syntax().children_with_tokens().count();
+        self.syntax().splice_children(0..count, children);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(text: &str) -> String {
+        let (mut changelog, _) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+        changelog.format().unwrap();
+        assert!(changelog.is_formatted());
+        changelog.to_string()
+    }
+
+    #[test]
+    fn test_formatted() {
+        let text = r#"breezy (3.3.4-1) unstable; urgency=low (fixes security issue), binary-only=yes
+
+  * New upstream release.
+
+  [ Jane Doe ]
+  * Fix a bug.
+    - In a sub-item.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+breezy (3.3.3-1) unstable experimental; urgency=low
+
+  * Old.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+        let mut changelog: ChangeLog = text.parse().unwrap();
+        assert!(changelog.is_formatted());
+        assert!(!changelog.format().unwrap());
+        assert_eq!(changelog.to_string(), text);
+    }
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(
+            format(
+                r#"# leading comment
+breezy (3.3.4-1)  unstable;  urgency=low  (foo bar),binary-only=yes
+
+
+  * New upstream release.
+
+
+  * This is a very long line that goes on and on and on and on and goes past 80 now.
+ -- Jelmer Vernooĳ <jelmer@debian.org>   Mon,  04 Sep 2023 18:13:45 -0500
+
+
+
+breezy (3.3.3-1) unstable; urgency=low
+  * Old.
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+
+# Local variables:
+# End:
+"#
+            ),
+            r#"# leading comment
+breezy (3.3.4-1) unstable; urgency=low (foo bar), binary-only=yes
+
+  * New upstream release.
+
+  * This is a very long line that goes on and on and on and on and goes past
+    80 now.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+breezy (3.3.3-1) unstable; urgency=low
+
+  * Old.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+# Local variables:
+# End:
+"#
+        );
+    }
+
+    #[test]
+    fn test_keep_unparseable() {
+        let text = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+breezy 3.3.3-1 unstable; urgency=low
+  * Old.
+"#;
+        let (changelog, _) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+        let formatted = format(text);
+        assert!(formatted.starts_with(
+            r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+"#
+        ));
+        assert!(formatted.ends_with(&changelog.to_string()[text.find("breezy 3").unwrap()..]));
+    }
+}
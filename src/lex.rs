@@ -5,6 +5,8 @@ use std::str::Chars;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum LineType {
     Header,
+    // The part of the header after the semicolon
+    Metadata,
     Body,
     Footer,
 }
@@ -73,9 +75,11 @@ impl<'a> Lexer<'a> {
     fn next_token(&mut self) -> Option<(SyntaxKind, String)> {
         if let Some(&c) = self.input.peek() {
             match (c, self.line_type) {
-                (c, None) | (c, Some(LineType::Header)) if Self::is_valid_identifier_char(c) => {
+                (c, None) | (c, Some(LineType::Header)) | (c, Some(LineType::Metadata))
+                    if Self::is_valid_identifier_char(c) =>
+                {
                     let identifier = self.read_while(Self::is_valid_identifier_char);
-                    self.line_type = Some(LineType::Header);
+                    self.line_type.get_or_insert(LineType::Header);
                     Some((SyntaxKind::IDENTIFIER, identifier))
                 }
                 (c, None) if Self::is_whitespace(c) => {
@@ -103,10 +107,13 @@ impl<'a> Lexer<'a> {
                     self.line_type = None;
                     Some((SyntaxKind::NEWLINE, c.to_string()))
                 }
-                (';', Some(LineType::Header)) => Some((
-                    SyntaxKind::SEMICOLON,
-                    self.input.next().unwrap().to_string(),
-                )),
+                (';', Some(LineType::Header)) => {
+                    self.line_type = Some(LineType::Metadata);
+                    Some((
+                        SyntaxKind::SEMICOLON,
+                        self.input.next().unwrap().to_string(),
+                    ))
+                }
                 ('(', Some(LineType::Header)) => {
                     let version = self
                         .read_while(|c| c != ')' && c != ';' && c != ' ' && !Self::is_newline(c));
@@ -122,9 +129,24 @@ impl<'a> Lexer<'a> {
                         Some((SyntaxKind::ERROR, version))
                     }
                 }
-                ('=', Some(LineType::Header)) => {
+                ('(', Some(LineType::Metadata)) => {
+                    // A comment following a value, e.g. "urgency=medium (was low)"
+                    let comment = self.read_while(|c| c != ')' && !Self::is_newline(c));
+                    let n = self.input.next();
+                    if n == Some(')') {
+                        Some((SyntaxKind::COMMENT, comment + ")"))
+                    } else if let Some(n) = n {
+                        Some((SyntaxKind::ERROR, comment + n.to_string().as_str()))
+                    } else {
+                        Some((SyntaxKind::ERROR, comment))
+                    }
+                }
+                ('=', Some(LineType::Metadata)) => {
                     Some((SyntaxKind::EQUALS, self.input.next().unwrap().to_string()))
                 }
+                (',', Some(LineType::Metadata)) => {
+                    Some((SyntaxKind::COMMA, self.input.next().unwrap().to_string()))
+                }
                 (_, Some(LineType::Body)) => {
                     let detail = self.read_while(|c| !Self::is_newline(c));
                     Some((SyntaxKind::DETAIL, detail))
@@ -239,4 +261,34 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_urgency_comment() {
+        assert_eq!(
+            super::lex("foo (1.0) unstable; urgency=MEDIUM (was low), bar=baz\n")
+                .iter()
+                .map(|(kind, text)| (*kind, text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (IDENTIFIER, "foo"),
+                (WHITESPACE, " "),
+                (VERSION, "(1.0)"),
+                (WHITESPACE, " "),
+                (IDENTIFIER, "unstable"),
+                (SEMICOLON, ";"),
+                (WHITESPACE, " "),
+                (IDENTIFIER, "urgency"),
+                (EQUALS, "="),
+                (IDENTIFIER, "MEDIUM"),
+                (WHITESPACE, " "),
+                (COMMENT, "(was low)"),
+                (COMMA, ","),
+                (WHITESPACE, " "),
+                (IDENTIFIER, "bar"),
+                (EQUALS, "="),
+                (IDENTIFIER, "baz"),
+                (NEWLINE, "\n"),
+            ]
+        );
+    }
 }
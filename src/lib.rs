@@ -21,15 +21,29 @@
 //!     .collect::<Vec<_>>());
 //! ```
 
+mod diagnostic;
 mod lex;
 mod parse;
 use lazy_regex::regex_captures;
+pub mod bugs;
 pub mod changes;
+pub mod commit;
+pub mod diff;
+pub mod distributions;
+pub mod format;
+pub mod lint;
+pub mod merge;
+pub mod parsechangelog;
+pub mod reproducible;
 pub mod textwrap;
+pub mod timestamp;
+pub mod tree;
+pub mod version;
 
-pub use crate::parse::{ChangeLog, Entry, Error, ParseError, Urgency};
+pub use crate::diagnostic::{Diagnostic, DiagnosticKind, Severity};
+pub use crate::parse::{ChangeLog, Entry, EntryBody, Error, ParseError, Urgency, UrgencyValue};
 
-/// See https://manpages.debian.org/bookworm/dpkg-dev/deb-changelog.5.en.html
+// See https://manpages.debian.org/bookworm/dpkg-dev/deb-changelog.5.en.html
 
 /// Let's start with defining all kinds of tokens and
 /// composite nodes.
@@ -44,10 +58,11 @@ pub enum SyntaxKind {
     VERSION,   // "(3.3.4-1)"
     SEMICOLON, // ";"
     EQUALS,    // "="
+    COMMA,     // ","
     DETAIL,    // "* New upstream release."
     NEWLINE,   // newlines are explicit
     ERROR,     // as well as errors
-    COMMENT,   // "#"
+    COMMENT,   // "#", or "(...)" following a metadata value
 
     // composite nodes
     ROOT,  // The entire file
@@ -287,10 +302,11 @@ const DEFAULT_DISTRIBUTION: &[&str] = &["UNRELEASED"];
 /// # Arguments
 /// * `cl` - The changelog to release
 /// * `distribution` - The distribution to release to. If None, the distribution
-///      of the previous entry is used.
-///  * `timestamp` - The timestamp to use for the release. If None, the current time is used.
-///  * `maintainer` - The maintainer to use for the release. If None, the maintainer
-///       is extracted from the environment.
+///   of the previous entry is used.
+/// * `timestamp` - The timestamp to use for the release. If None, `SOURCE_DATE_EPOCH` or
+///   the current time is used.
+/// * `maintainer` - The maintainer to use for the release. If None, the maintainer
+///   is extracted from the environment.
 ///
 /// # Returns
 /// Whether a release was created.
@@ -319,7 +335,7 @@ pub fn release(
     if first_entry.is_unreleased() == Some(false) {
         take_uploadership(&mut first_entry, maintainer);
         first_entry.set_distributions(distribution);
-        let timestamp = timestamp.unwrap_or(chrono::offset::Utc::now().into());
+        let timestamp = timestamp.unwrap_or_else(crate::reproducible::now);
         first_entry.set_datetime(timestamp);
         true
     } else {
@@ -351,3 +367,36 @@ pub fn take_uploadership(entry: &mut Entry, maintainer: Option<(String, String)>
     }
     entry.set_maintainer((maintainer_name, maintainer_email));
 }
+
+#[cfg(test)]
+mod take_uploadership_tests {
+    use super::*;
+
+    #[test]
+    fn test_take_uploadership() {
+        let cl: ChangeLog = r#"lintian-brush (0.35) UNRELEASED; urgency=medium
+
+  * Some change.
+
+ -- Joe Example <joe@example.com>  Fri, 04 Oct 2019 02:36:13 +0000
+"#
+        .parse()
+        .unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        take_uploadership(
+            &mut entry,
+            Some(("Jane Example".to_string(), "jane@example.com".to_string())),
+        );
+        assert_eq!(entry.maintainer(), Some("Jane Example".to_string()));
+        assert_eq!(entry.email(), Some("jane@example.com".to_string()));
+        assert_eq!(
+            cl.to_string(),
+            r#"lintian-brush (0.35) UNRELEASED; urgency=medium
+
+  * Some change.
+
+ -- Jane Example <jane@example.com>  Fri, 04 Oct 2019 02:36:13 +0000
+"#
+        );
+    }
+}
@@ -0,0 +1,1166 @@
+//! Checks for common problems in changelogs, similar to those done by lintian.
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::ChangeLog;
+//! use debian_changelog::lint::{Check, Linter};
+//!
+//! let changelog: ChangeLog = r#"breezy (3.3.4-1) unstable; urgency=low
+//!
+//!   * New upstream release.
+//!
+//!  -- Jelmer Vernooĳ <jelmer@localhost>  Mon, 04 Sep 2023 18:13:45 -0500
+//! "#.parse().unwrap();
+//! let lints = changelog.lint();
+//! assert_eq!(lints.len(), 1);
+//! assert_eq!(lints[0].check, Check::InvalidEmail);
+//! assert_eq!(
+//!     lints[0].to_string(),
+//!     "5:20: error: debian-changelog-file-contains-invalid-email-address: \
+//!      invalid email address: jelmer@localhost"
+//! );
+//!
+//! let linter = Linter::default().disable(Check::InvalidEmail);
+//! assert!(linter.lint(&changelog).is_empty());
+//! ```
+
+use crate::diagnostic::line_column;
+use crate::distributions::DistributionRegistry;
+use crate::parse::{new_node, new_token, SyntaxElement, SyntaxNode};
+use crate::timestamp::Deviation;
+use crate::{ChangeLog, Entry, Severity, SyntaxKind};
+use chrono::{DateTime, FixedOffset};
+use rowan::ast::AstNode;
+use rowan::{NodeOrToken, TextRange, TextSize};
+use std::collections::HashSet;
+use textwrap::core::display_width;
+
+/// A check performed by the linter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Check {
+    /// An entry has a version that is not lower than that of the entry above it.
+    VersionOrder,
+    /// An entry has a timestamp that is later than that of the entry above it.
+    TimestampOrder,
+    /// The email address and the date in the footer are not separated by two spaces.
+    ///
+    /// Lintian reports a badly formatted footer as a syntax error, so this uses its generic
+    /// `syntax-error-in-debian-changelog` tag.
+    FooterSeparator,
+    /// The date in the footer is not a valid RFC 2822 date.
+    InvalidDate,
+    /// The day of the week in the footer does not match the date.
+    WrongDayOfWeek,
+    /// The date in the footer is valid, but not in the canonical format.
+    NonCanonicalDate,
+    /// A line ends in whitespace.
+    TrailingWhitespace,
+    /// A line in the body of an entry is too long.
+    LineTooLong,
+    /// An entry is marked as UNRELEASED, but an entry above it has been released.
+    UnreleasedBelowReleased,
+    /// The package name differs from that of the entry above it.
+    PackageNameChanged,
+    /// An entry has no changes.
+    EmptyEntry,
+    /// The email address in the footer is not valid.
+    InvalidEmail,
+    /// The urgency is not in lowercase.
+    UrgencyCase,
+    /// A distribution is not a known Debian or Ubuntu suite.
+    UnknownDistribution,
+}
+
+impl Check {
+    /// All checks, in the order in which they are run.
+    pub const ALL: &'static [Check] = &[
+        Check::VersionOrder,
+        Check::TimestampOrder,
+        Check::FooterSeparator,
+        Check::InvalidDate,
+        Check::WrongDayOfWeek,
+        Check::NonCanonicalDate,
+        Check::TrailingWhitespace,
+        Check::LineTooLong,
+        Check::UnreleasedBelowReleased,
+        Check::PackageNameChanged,
+        Check::EmptyEntry,
+        Check::InvalidEmail,
+        Check::UrgencyCase,
+        Check::UnknownDistribution,
+    ];
+
+    /// The stable name of the check, in the style of lintian tags.
+    ///
+    /// Where lintian reports the same problem, this is lintian's tag: for
+    /// [`Check::FooterSeparator`], [`Check::InvalidDate`], [`Check::WrongDayOfWeek`],
+    /// [`Check::TrailingWhitespace`], [`Check::LineTooLong`], [`Check::InvalidEmail`] and
+    /// [`Check::UnknownDistribution`]. The tags of the other checks, which lintian does not
+    /// have, start with `debian-changelog-rs/`. The ordering checks look at every entry rather
+    /// than only the latest one, so they do not use lintian's `latest-debian-changelog-entry-*`
+    /// tags either.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Check::VersionOrder => "debian-changelog-rs/version-not-decreasing",
+            Check::TimestampOrder => "debian-changelog-rs/timestamps-out-of-order",
+            Check::FooterSeparator => "syntax-error-in-debian-changelog",
+            Check::InvalidDate => "invalid-date-in-debian-changelog",
+            Check::WrongDayOfWeek => "debian-changelog-has-wrong-day-of-week",
+            Check::NonCanonicalDate => "debian-changelog-rs/non-canonical-date",
+            Check::TrailingWhitespace => "file-contains-trailing-whitespace",
+            Check::LineTooLong => "debian-changelog-line-too-long",
+            Check::UnreleasedBelowReleased => "debian-changelog-rs/unreleased-below-released",
+            Check::PackageNameChanged => "debian-changelog-rs/package-name-changed",
+            Check::EmptyEntry => "debian-changelog-rs/entry-without-changes",
+            Check::InvalidEmail => "debian-changelog-file-contains-invalid-email-address",
+            Check::UrgencyCase => "debian-changelog-rs/urgency-not-lowercase",
+            Check::UnknownDistribution => "bad-distribution-in-debian-changelog",
+        }
+    }
+
+    /// The severity of problems found by the check.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Check::VersionOrder
+            | Check::InvalidDate
+            | Check::UnreleasedBelowReleased
+            | Check::InvalidEmail => Severity::Error,
+            Check::TimestampOrder
+            | Check::FooterSeparator
+            | Check::WrongDayOfWeek
+            | Check::NonCanonicalDate
+            | Check::TrailingWhitespace
+            | Check::LineTooLong
+            | Check::PackageNameChanged
+            | Check::EmptyEntry
+            | Check::UrgencyCase
+            | Check::UnknownDistribution => Severity::Warning,
+        }
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl std::str::FromStr for Check {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Check::ALL
+            .iter()
+            .find(|c| c.tag() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown check: {}", s))
+    }
+}
+
+/// A problem found by the linter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lint {
+    pub check: Check,
+    pub severity: Severity,
+    pub message: String,
+    /// The byte range in the changelog text.
+    pub range: TextRange,
+    /// The line the range starts on, counting from 1.
+    pub line: usize,
+    /// The column the range starts at, in characters and counting from 1.
+    pub column: usize,
+    /// A fix that can be applied automatically, if there is one.
+    pub fix: Option<Fix>,
+}
+
+impl Lint {
+    /// The stable name of the check that found the problem.
+    pub fn tag(&self) -> &'static str {
+        self.check.tag()
+    }
+
+    /// Apply the fix for this problem, if there is one.
+    ///
+    /// The changelog should not have been modified since it was linted.
+    /// Returns whether the changelog was changed.
+    pub fn apply_fix(&self, changelog: &mut ChangeLog) -> bool {
+        self.fix
+            .as_ref()
+            .and_then(|fix| fix.apply(changelog, self.range))
+            .is_some()
+    }
+}
+
+/// A machine-applicable fix for a problem found by the linter.
+///
+/// Fixes edit the tree in place, leaving everything outside of the range of
+/// the problem untouched.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Fix {
+    /// Separate the email address and the date in the footer with two spaces.
+    RespaceFooter,
+    /// Replace the date with the given one, in the canonical format.
+    ReformatDate(DateTime<FixedOffset>),
+    /// Remove the whitespace at the end of the line.
+    StripTrailingWhitespace,
+    /// Rewrap the change that contains the line, so that its lines (without the indentation
+    /// of the entry body) are at most this many columns wide.
+    RewrapChange(usize),
+    /// Convert the urgency to lowercase.
+    LowercaseUrgency,
+}
+
+/// Return the innermost node of the given kind that contains a range.
+fn ancestor(changelog: &ChangeLog, range: TextRange, kind: SyntaxKind) -> Option<SyntaxNode> {
+    if !changelog.syntax().text_range().contains_range(range) {
+        return None;
+    }
+    let node = match changelog.syntax().covering_element(range) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent()?,
+    };
+    node.ancestors().find(|n| n.kind() == kind)
+}
+
+/// Replace a child of a node.
+fn replace_child(parent: &SyntaxNode, index: usize, new: Vec<SyntaxElement>) {
+    parent.splice_children(index..index + 1, new);
+}
+
+/// Return the text of the DETAIL token in an ENTRY_BODY node.
+fn detail(body: &SyntaxNode) -> Option<String> {
+    body.children_with_tokens()
+        .find(|it| it.kind() == SyntaxKind::DETAIL)
+        .and_then(|it| it.as_token().map(|t| t.text().to_string()))
+}
+
+/// Return the ENTRY_BODY nodes that make up the bulleted change that contains a line.
+fn change_nodes(body: &SyntaxNode) -> Option<Vec<SyntaxNode>> {
+    let bullet = |line: &str| lazy_regex::regex_find!(r"^\s*[\+\-\*] ", line).map(|b| b.len());
+    let is_body = |n: &SyntaxNode| n.kind() == SyntaxKind::ENTRY_BODY;
+
+    let mut nodes = vec![body.clone()];
+    let indent = loop {
+        if let Some(indent) = bullet(detail(&nodes[0])?.as_str()) {
+            break indent;
+        }
+        nodes.insert(0, nodes[0].prev_sibling().filter(is_body)?);
+    };
+    let prefix = " ".repeat(indent);
+    if nodes[1..]
+        .iter()
+        .any(|n| !detail(n).is_some_and(|l| l.starts_with(prefix.as_str())))
+    {
+        return None;
+    }
+    while let Some(next) = nodes.last().unwrap().next_sibling().filter(is_body) {
+        match detail(&next) {
+            Some(line) if line.starts_with(prefix.as_str()) && bullet(line.as_str()).is_none() => {
+                nodes.push(next);
+            }
+            _ => break,
+        }
+    }
+    Some(nodes)
+}
+
+impl Fix {
+    /// Apply the fix for a problem found at `range`.
+    ///
+    /// Returns the range of the original text that was replaced, or None if
+    /// the fix could not be applied.
+    fn apply(&self, changelog: &mut ChangeLog, range: TextRange) -> Option<TextRange> {
+        match self {
+            Fix::RespaceFooter => {
+                let footer = ancestor(changelog, range, SyntaxKind::ENTRY_FOOTER)?;
+                let timestamp = footer
+                    .children()
+                    .find(|n| n.kind() == SyntaxKind::TIMESTAMP)?;
+                let separator = new_token(SyntaxKind::WHITESPACE, "  ");
+                match timestamp.prev_sibling_or_token() {
+                    Some(previous)
+                        if previous.kind() == SyntaxKind::WHITESPACE
+                            || (previous.kind() == SyntaxKind::ERROR
+                                && previous
+                                    .as_node()
+                                    .is_some_and(|n| n.text().to_string().trim().is_empty())) =>
+                    {
+                        replace_child(&footer, previous.index(), vec![separator]);
+                        Some(previous.text_range())
+                    }
+                    _ => {
+                        let index = timestamp.index();
+                        footer.splice_children(index..index, vec![separator]);
+                        Some(TextRange::empty(timestamp.text_range().start()))
+                    }
+                }
+            }
+            Fix::ReformatDate(datetime) => {
+                let mut entry = Entry::cast(ancestor(changelog, range, SyntaxKind::ENTRY)?)?;
+                entry.set_datetime(*datetime);
+                Some(range)
+            }
+            Fix::StripTrailingWhitespace => {
+                let tokens = changelog
+                    .syntax()
+                    .descendants_with_tokens()
+                    .filter_map(|it| it.into_token())
+                    .filter(|t| {
+                        t.text_range()
+                            .intersect(range)
+                            .is_some_and(|r| !r.is_empty())
+                    })
+                    .collect::<Vec<_>>();
+                if tokens.is_empty() {
+                    return None;
+                }
+                for token in tokens.into_iter().rev() {
+                    let strip =
+                        token.text_range().intersect(range).unwrap() - token.text_range().start();
+                    let mut text = token.text().to_string();
+                    text.replace_range(std::ops::Range::<usize>::from(strip), "");
+                    let parent = token.parent()?;
+                    let new = if text.is_empty() {
+                        vec![]
+                    } else {
+                        vec![new_token(token.kind(), text.as_str())]
+                    };
+                    replace_child(&parent, token.index(), new);
+                }
+                Some(range)
+            }
+            Fix::RewrapChange(width) => {
+                let body = ancestor(changelog, range, SyntaxKind::ENTRY_BODY)?;
+                let nodes = change_nodes(&body)?;
+                let lines = nodes.iter().filter_map(detail).collect::<Vec<_>>();
+                let wrapped = crate::textwrap::Wrapper::new()
+                    .width(*width)
+                    .rewrap_changes_lenient(lines.iter().map(|l| l.as_str()))
+                    .into_iter()
+                    .map(|l| l.to_string())
+                    .collect::<Vec<_>>();
+                if wrapped == lines {
+                    return None;
+                }
+                let first = nodes.first().unwrap();
+                let last = nodes.last().unwrap();
+                let new = wrapped
+                    .iter()
+                    .map(|line| {
+                        new_node(SyntaxKind::ENTRY_BODY, |builder| {
+                            builder.token(SyntaxKind::INDENT.into(), "  ");
+                            builder.token(SyntaxKind::DETAIL.into(), line.as_str());
+                            builder.token(SyntaxKind::NEWLINE.into(), "\n");
+                        })
+                    })
+                    .collect();
+                first
+                    .parent()?
+                    .splice_children(first.index()..last.index() + 1, new);
+                Some(TextRange::new(
+                    first.text_range().start(),
+                    last.text_range().end(),
+                ))
+            }
+            Fix::LowercaseUrgency => {
+                let NodeOrToken::Token(token) = changelog.syntax().covering_element(range) else {
+                    return None;
+                };
+                let new = new_token(token.kind(), token.text().to_lowercase().as_str());
+                replace_child(&token.parent()?, token.index(), vec![new]);
+                Some(token.text_range())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}: {}",
+            self.line,
+            self.column,
+            self.severity,
+            self.tag(),
+            self.message
+        )
+    }
+}
+
+/// Runs a configurable set of checks over a changelog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Linter {
+    checks: HashSet<Check>,
+    max_line_length: usize,
+    // None for the registry of the system, which is only loaded when it is needed
+    distributions: Option<DistributionRegistry>,
+}
+
+impl Default for Linter {
+    /// A linter with all checks enabled, a maximum line length of 80 and the
+    /// distributions known to the system (see [`DistributionRegistry::system`]).
+    fn default() -> Self {
+        Self {
+            checks: Check::ALL.iter().copied().collect(),
+            max_line_length: 80,
+            distributions: None,
+        }
+    }
+}
+
+/// Return the first direct child token of a node with the given kind.
+fn child_token(node: &SyntaxNode, kind: SyntaxKind) -> Option<TextRange> {
+    node.children_with_tokens()
+        .find(|it| it.kind() == kind)
+        .map(|it| it.text_range())
+}
+
+fn header(entry: &Entry) -> Option<SyntaxNode> {
+    entry
+        .syntax()
+        .children()
+        .find(|n| n.kind() == SyntaxKind::ENTRY_HEADER)
+}
+
+fn footer(entry: &Entry) -> Option<SyntaxNode> {
+    entry
+        .syntax()
+        .children()
+        .find(|n| n.kind() == SyntaxKind::ENTRY_FOOTER)
+}
+
+/// The range of the header, without the trailing newline.
+fn header_range(entry: &Entry) -> TextRange {
+    let range = header(entry).map_or(entry.syntax().text_range(), |h| h.text_range());
+    let len = entry.header_line().map_or(0, |l| l.len());
+    TextRange::at(range.start(), TextSize::from(len as u32))
+}
+
+fn is_valid_email(email: &str) -> bool {
+    lazy_regex::regex_is_match!(r"^[^@\s<>]+@[^@\s<>.]+(\.[^@\s<>.]+)+$", email)
+}
+
+impl Linter {
+    /// Create a linter with all checks enabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a linter that only runs the given checks.
+    pub fn only(checks: &[Check]) -> Self {
+        Self {
+            checks: checks.iter().copied().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Enable a check.
+    pub fn enable(mut self, check: Check) -> Self {
+        self.checks.insert(check);
+        self
+    }
+
+    /// Disable a check.
+    pub fn disable(mut self, check: Check) -> Self {
+        self.checks.remove(&check);
+        self
+    }
+
+    /// Set the maximum length of lines in the body of entries, in terminal columns.
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = max_line_length;
+        self
+    }
+
+    /// Set the registry used to check distributions.
+    pub fn distributions(mut self, distributions: DistributionRegistry) -> Self {
+        self.distributions = Some(distributions);
+        self
+    }
+
+    fn registry(&self) -> &DistributionRegistry {
+        self.distributions
+            .as_ref()
+            .unwrap_or_else(|| DistributionRegistry::system())
+    }
+
+    /// Return whether a check is enabled.
+    pub fn is_enabled(&self, check: Check) -> bool {
+        self.checks.contains(&check)
+    }
+
+    /// Run the enabled checks over a changelog.
+    ///
+    /// The problems are returned in the order in which they appear in the text.
+    pub fn lint(&self, changelog: &ChangeLog) -> Vec<Lint> {
+        let source = changelog.syntax().text().to_string();
+        let mut lints = vec![];
+        let mut add = |check: Check, range: TextRange, message: String, fix: Option<Fix>| {
+            if !self.is_enabled(check) {
+                return;
+            }
+            let (line, column) = line_column(&source, range.start());
+            lints.push(Lint {
+                check,
+                severity: check.severity(),
+                message,
+                range,
+                line,
+                column,
+                fix,
+            });
+        };
+
+        let entries = changelog.entries().collect::<Vec<_>>();
+        let mut seen_released = false;
+        for (i, entry) in entries.iter().enumerate() {
+            let header = header(entry);
+            let footer = footer(entry);
+            let previous = i.checked_sub(1).map(|i| &entries[i]);
+
+            if let Some(previous) = previous {
+                if let (Some(newer), Some(older)) = (previous.version(), entry.version()) {
+                    if older >= newer {
+                        let range = header
+                            .as_ref()
+                            .and_then(|h| child_token(h, SyntaxKind::VERSION))
+                            .unwrap_or_else(|| header_range(entry));
+                        add(
+                            Check::VersionOrder,
+                            range,
+                            format!(
+                                "version {} is not lower than {} of the entry above",
+                                older.to_string(),
+                                newer.to_string()
+                            ),
+                            None,
+                        );
+                    }
+                }
+
+                if let (Some(newer), Some(older)) = (previous.datetime(), entry.datetime()) {
+                    if older > newer {
+                        let range = footer
+                            .as_ref()
+                            .and_then(|f| child_token(f, SyntaxKind::TIMESTAMP))
+                            .unwrap_or_else(|| entry.syntax().text_range());
+                        add(
+                            Check::TimestampOrder,
+                            range,
+                            "timestamp is later than that of the entry above".to_string(),
+                            None,
+                        );
+                    }
+                }
+
+                if let (Some(newer), Some(older)) = (previous.package(), entry.package()) {
+                    if newer != older {
+                        let range = header
+                            .as_ref()
+                            .and_then(|h| child_token(h, SyntaxKind::IDENTIFIER))
+                            .unwrap_or_else(|| header_range(entry));
+                        add(
+                            Check::PackageNameChanged,
+                            range,
+                            format!("package name {} differs from {} above", older, newer),
+                            None,
+                        );
+                    }
+                }
+            }
+
+            if let Some(distributions) = entry.distributions() {
+                let distributions = distributions.iter().map(|d| d.as_str()).collect::<Vec<_>>();
+                if crate::distributions_is_unreleased(distributions.as_slice()) {
+                    if seen_released {
+                        let range = header
+                            .as_ref()
+                            .and_then(|h| child_token(h, SyntaxKind::DISTRIBUTIONS))
+                            .unwrap_or_else(|| header_range(entry));
+                        add(
+                            Check::UnreleasedBelowReleased,
+                            range,
+                            "unreleased entry below a released entry".to_string(),
+                            None,
+                        );
+                    }
+                } else {
+                    seen_released = true;
+                    for token in header
+                        .iter()
+                        .flat_map(|h| h.children())
+                        .filter(|n| n.kind() == SyntaxKind::DISTRIBUTIONS)
+                        .flat_map(|n| n.children_with_tokens())
+                        .filter_map(|it| it.into_token())
+                        .filter(|t| t.kind() == SyntaxKind::IDENTIFIER)
+                    {
+                        // Suites like "stable" are resolved at the time of the upload
+                        let date = entry
+                            .datetime()
+                            .unwrap_or_else(crate::reproducible::now)
+                            .date_naive();
+                        if !self.registry().is_known_at(token.text(), date) {
+                            add(
+                                Check::UnknownDistribution,
+                                token.text_range(),
+                                format!("unknown distribution: {}", token.text()),
+                                None,
+                            );
+                        }
+                    }
+                }
+            }
+
+            for value in header
+                .iter()
+                .flat_map(|h| h.descendants())
+                .filter(|n| n.kind() == SyntaxKind::METADATA_ENTRY)
+                .filter(|n| {
+                    n.children().any(|k| {
+                        k.kind() == SyntaxKind::METADATA_KEY
+                            && k.text().to_string().eq_ignore_ascii_case("urgency")
+                    })
+                })
+                .filter_map(|n| {
+                    n.children()
+                        .find(|v| v.kind() == SyntaxKind::METADATA_VALUE)
+                })
+            {
+                if let Some(urgency) = value
+                    .children_with_tokens()
+                    .filter_map(|it| it.into_token())
+                    .find(|t| t.kind() == SyntaxKind::IDENTIFIER)
+                {
+                    if urgency.text() != urgency.text().to_lowercase() {
+                        add(
+                            Check::UrgencyCase,
+                            urgency.text_range(),
+                            format!("urgency {} is not in lowercase", urgency.text()),
+                            Some(Fix::LowercaseUrgency),
+                        );
+                    }
+                }
+            }
+
+            if header.is_some() && entry.change_lines().all(|l| l.trim().is_empty()) {
+                add(
+                    Check::EmptyEntry,
+                    header_range(entry),
+                    "entry has no changes".to_string(),
+                    None,
+                );
+            }
+
+            if let Some(footer) = footer.as_ref() {
+                if let Some(timestamp) = footer
+                    .children()
+                    .find(|n| n.kind() == SyntaxKind::TIMESTAMP)
+                {
+                    let separator = timestamp.prev_sibling_or_token();
+                    let valid = separator.as_ref().is_some_and(|s| {
+                        s.kind() == SyntaxKind::WHITESPACE
+                            && s.as_token().is_some_and(|t| t.text() == "  ")
+                    });
+                    if !valid {
+                        let range = separator
+                            .map_or(TextRange::empty(timestamp.text_range().start()), |s| {
+                                s.text_range()
+                            });
+                        add(
+                            Check::FooterSeparator,
+                            range,
+                            "email and date should be separated by two spaces".to_string(),
+                            Some(Fix::RespaceFooter),
+                        );
+                    }
+
+                    match entry.parse_timestamp() {
+                        Some(Err(e)) => add(
+                            Check::InvalidDate,
+                            timestamp.text_range(),
+                            format!("invalid date: {}: {}", timestamp.text(), e),
+                            None,
+                        ),
+                        Some(Ok(parsed)) if !parsed.is_canonical() => add(
+                            if parsed
+                                .deviations
+                                .iter()
+                                .any(|d| matches!(d, Deviation::WrongWeekday { .. }))
+                            {
+                                Check::WrongDayOfWeek
+                            } else {
+                                Check::NonCanonicalDate
+                            },
+                            timestamp.text_range(),
+                            format!(
+                                "non-canonical date: {}: {}",
+                                timestamp.text(),
+                                parsed
+                                    .deviations
+                                    .iter()
+                                    .map(|d| d.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
+                            Some(Fix::ReformatDate(parsed.datetime)),
+                        ),
+                        _ => {}
+                    }
+                }
+
+                if let Some(email) = footer
+                    .descendants_with_tokens()
+                    .find(|it| it.kind() == SyntaxKind::EMAIL)
+                {
+                    let text = entry.email().unwrap_or_default();
+                    if !is_valid_email(text.as_str()) {
+                        add(
+                            Check::InvalidEmail,
+                            email.text_range(),
+                            format!("invalid email address: {}", text),
+                            None,
+                        );
+                    }
+                }
+            }
+
+            for body in entry
+                .syntax()
+                .children()
+                .filter(|n| n.kind() == SyntaxKind::ENTRY_BODY)
+            {
+                let text = body.text().to_string();
+                let line = text.trim_end_matches(['\r', '\n']);
+                let length = display_width(line);
+                if length > self.max_line_length {
+                    // The first character that does not fit
+                    let mut width = 0;
+                    let start = line
+                        .char_indices()
+                        .find(|(i, c)| {
+                            width += display_width(&line[*i..i + c.len_utf8()]);
+                            width > self.max_line_length
+                        })
+                        .map_or(line.len(), |(i, _)| i);
+                    add(
+                        Check::LineTooLong,
+                        TextRange::new(
+                            body.text_range().start() + TextSize::from(start as u32),
+                            body.text_range().start() + TextSize::from(line.len() as u32),
+                        ),
+                        format!(
+                            "line is {} columns wide (more than {})",
+                            length, self.max_line_length
+                        ),
+                        // Changes are indented by two columns in the entry body
+                        change_nodes(&body)
+                            .map(|_| Fix::RewrapChange(self.max_line_length.saturating_sub(2))),
+                    );
+                }
+            }
+        }
+
+        let mut offset = 0;
+        for line in source.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\r', '\n']);
+            let trimmed = content.trim_end();
+            if trimmed.len() < content.len() {
+                add(
+                    Check::TrailingWhitespace,
+                    TextRange::new(
+                        TextSize::from((offset + trimmed.len()) as u32),
+                        TextSize::from((offset + content.len()) as u32),
+                    ),
+                    "trailing whitespace".to_string(),
+                    Some(Fix::StripTrailingWhitespace),
+                );
+            }
+            offset += line.len();
+        }
+
+        lints.sort_by_key(|l| (l.range.start(), l.check));
+        lints
+    }
+
+    /// Apply the fixes for all problems found by the enabled checks.
+    ///
+    /// Returns the problems that were fixed.
+    pub fn fix(&self, changelog: &mut ChangeLog) -> Vec<Lint> {
+        self.fix_matching(changelog, |_| true)
+    }
+
+    /// Apply the fixes for the problems found by the checks with the given tags.
+    ///
+    /// Returns the problems that were fixed.
+    pub fn fix_tags(&self, changelog: &mut ChangeLog, tags: &[&str]) -> Vec<Lint> {
+        self.fix_matching(changelog, |lint| tags.contains(&lint.tag()))
+    }
+
+    fn fix_matching(&self, changelog: &mut ChangeLog, filter: impl Fn(&Lint) -> bool) -> Vec<Lint> {
+        let mut fixed = vec![];
+        let mut remaining = usize::MAX;
+        loop {
+            let lints = self
+                .lint(changelog)
+                .into_iter()
+                .filter(|l| l.fix.is_some() && filter(l))
+                .collect::<Vec<_>>();
+            // Stop if the previous round did not make any progress
+            if lints.len() >= remaining {
+                break;
+            }
+            remaining = lints.len();
+            // Apply the fixes from the end, so that the ranges of earlier
+            // problems remain valid. Fixes that overlap with text that has
+            // already been changed are left for the next round.
+            let mut changed_from = changelog.syntax().text_range().end();
+            for lint in lints.into_iter().rev() {
+                if lint.range.end() > changed_from {
+                    continue;
+                }
+                if let Some(changed) = lint.fix.as_ref().unwrap().apply(changelog, lint.range) {
+                    changed_from = changed.start();
+                    fixed.push(lint);
+                }
+            }
+        }
+        fixed
+    }
+}
+
+impl ChangeLog {
+    /// Check the changelog for common problems, using all checks.
+    pub fn lint(&self) -> Vec<Lint> {
+        Linter::default().lint(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linter() -> Linter {
+        Linter::new().distributions(DistributionRegistry::builtin())
+    }
+
+    fn tags(text: &str) -> Vec<(usize, &'static str)> {
+        let (changelog, _) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+        linter()
+            .lint(&changelog)
+            .into_iter()
+            .map(|l| (l.line, l.tag()))
+            .collect()
+    }
+
+    #[test]
+    fn test_clean() {
+        assert_eq!(
+            tags(
+                r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+breezy (3.3.3-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 17 Jun 2023 14:58:57 +0100
+"#
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_order() {
+        assert_eq!(
+            tags(
+                r#"breezy (3.3.3-1) UNRELEASED; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 17 Jun 2023 14:58:57 +0100
+
+brz (3.3.4-1) UNRELEASED; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+            ),
+            vec![
+                (7, "debian-changelog-rs/package-name-changed"),
+                (7, "debian-changelog-rs/version-not-decreasing"),
+                (11, "debian-changelog-rs/timestamps-out-of-order"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unreleased_below_released() {
+        assert_eq!(
+            tags(
+                r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+breezy (3.3.3-1) UNRELEASED; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 17 Jun 2023 14:58:57 +0100
+"#
+            ),
+            vec![(7, "debian-changelog-rs/unreleased-below-released")]
+        );
+    }
+
+    #[test]
+    fn test_footer() {
+        assert_eq!(
+            tags(
+                r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@localhost> Mon, 31 Sep 2023 18:13:45 -0500
+"#
+            ),
+            vec![
+                (5, "debian-changelog-file-contains-invalid-email-address"),
+                (5, "syntax-error-in-debian-changelog"),
+                (5, "invalid-date-in-debian-changelog"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_non_canonical_date() {
+        let text = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 4 Sep 2023 23:13:45 GMT
+
+breezy (3.3.3-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sun, 17 Jun 2023 14:58:57 +0100
+"#;
+        assert_eq!(
+            tags(text),
+            vec![
+                (5, "debian-changelog-rs/non-canonical-date"),
+                (11, "debian-changelog-has-wrong-day-of-week"),
+            ]
+        );
+        let changelog: ChangeLog = text.parse().unwrap();
+        assert!(linter()
+            .lint(&changelog)
+            .iter()
+            .all(|l| l.check.severity() == Severity::Warning));
+    }
+
+    #[test]
+    fn test_body() {
+        assert_eq!(
+            tags(
+                r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * This is a very long line that goes on and on and on and on and goes past 80 now.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+breezy (3.3.3-1) unstable; urgency=low
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 17 Jun 2023 14:58:57 +0100
+"#
+            ),
+            vec![
+                (3, "debian-changelog-line-too-long"),
+                (7, "debian-changelog-rs/entry-without-changes"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_distribution() {
+        let changelog: ChangeLog =
+            r#"breezy (3.3.4-1) bookworm-backports stable-security bookwrom; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+            .parse()
+            .unwrap();
+        let lints = Linter::only(&[Check::UnknownDistribution])
+            .distributions(DistributionRegistry::builtin())
+            .lint(&changelog);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(&changelog.to_string()[lints[0].range], "bookwrom");
+        assert_eq!(lints[0].message, "unknown distribution: bookwrom");
+
+        let changelog: ChangeLog = r#"dpkg (1.4.1.1) frozen unstable; urgency=low
+
+  * Fix a bug.
+
+ -- Jane Example <jane@example.com>  Mon, 09 Nov 1998 18:13:45 +0000
+"#
+        .parse()
+        .unwrap();
+        assert!(Linter::only(&[Check::UnknownDistribution])
+            .distributions(DistributionRegistry::builtin())
+            .lint(&changelog)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_trailing_whitespace() {
+        let text = "breezy (3.3.4-1) unstable; urgency=low \n\n  * New upstream release.\t\r\n\n -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500\n";
+        let changelog: ChangeLog = text.parse().unwrap();
+        let ranges = Linter::only(&[Check::TrailingWhitespace])
+            .lint(&changelog)
+            .into_iter()
+            .map(|l| (l.line, &text[l.range]))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(1, " "), (3, "\t")]);
+    }
+
+    #[test]
+    fn test_configure() {
+        let changelog: ChangeLog = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * A line that is a bit long.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        let lints = linter().max_line_length(20).lint(&changelog);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].check, Check::LineTooLong);
+        assert_eq!(&changelog.to_string()[lints[0].range], " bit long.");
+
+        // Wide characters take up two columns
+        let changelog: ChangeLog = changelog
+            .to_string()
+            .replace("a bit", "漢字字")
+            .parse()
+            .unwrap();
+        let lints = linter().max_line_length(20).lint(&changelog);
+        assert_eq!(&changelog.to_string()[lints[0].range], "漢字字 long.");
+        assert!(Linter::only(&[Check::TrailingWhitespace])
+            .max_line_length(20)
+            .lint(&changelog)
+            .is_empty());
+        assert_eq!(
+            "file-contains-trailing-whitespace".parse::<Check>(),
+            Ok(Check::TrailingWhitespace)
+        );
+    }
+
+    #[test]
+    fn test_fix_line_length() {
+        let mut changelog: ChangeLog = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * A line that is a bit long.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        let linter = linter().max_line_length(20);
+        let lints = linter.lint(&changelog);
+        assert_eq!(lints[0].fix, Some(Fix::RewrapChange(18)));
+        assert!(lints[0].apply_fix(&mut changelog));
+        assert_eq!(
+            changelog
+                .entries()
+                .next()
+                .unwrap()
+                .change_lines()
+                .collect::<Vec<_>>(),
+            vec!["* A line that is a", "  bit long."]
+        );
+        assert!(linter.lint(&changelog).is_empty());
+    }
+
+    #[test]
+    fn test_fix_all() {
+        let text = "breezy (3.3.4-1) unstable; urgency=LOW\n\n  * New upstream release. \n  * This is a very long line that goes on and on and on and on and goes past 80 now.\n    (Closes: #123)\n\n -- Jelmer Vernooĳ <jelmer@debian.org> Sat, 04 Sep 2023 18:13:45 -0500\n";
+        let (mut changelog, _) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+        let fixed = linter().fix(&mut changelog);
+        assert_eq!(
+            fixed.iter().map(|l| l.tag()).collect::<HashSet<_>>(),
+            HashSet::from([
+                "debian-changelog-rs/urgency-not-lowercase",
+                "file-contains-trailing-whitespace",
+                "debian-changelog-line-too-long",
+                "syntax-error-in-debian-changelog",
+                "debian-changelog-has-wrong-day-of-week",
+            ])
+        );
+        assert_eq!(
+            changelog.to_string(),
+            r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+  * This is a very long line that goes on and on and on and on and goes past 80
+    now. (Closes: #123)
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        );
+        assert_eq!(linter().lint(&changelog), vec![]);
+    }
+
+    #[test]
+    fn test_fix_tags() {
+        let text = "breezy (3.3.4-1) unstable; urgency=Medium (Security fix) \n\n  * New upstream release.\n\n -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500\n";
+        let mut changelog: ChangeLog = text.parse().unwrap();
+        let fixed = linter().fix_tags(
+            &mut changelog,
+            &["debian-changelog-rs/urgency-not-lowercase"],
+        );
+        assert_eq!(fixed.len(), 1);
+        assert_eq!(
+            changelog.to_string(),
+            text.replace("urgency=Medium", "urgency=medium")
+        );
+        let lints = linter().lint(&changelog);
+        assert_eq!(lints.len(), 1);
+        assert!(lints[0].apply_fix(&mut changelog));
+        assert_eq!(
+            changelog.to_string(),
+            text.replace("urgency=Medium", "urgency=medium")
+                .replace(") \n", ")\n")
+        );
+    }
+
+    #[test]
+    fn test_no_fix() {
+        let (mut changelog, _) = ChangeLog::read_relaxed(
+            r#"breezy (3.3.4-1) unstable; urgency=low
+
+  [ This is a very long line that is not a change, so it can not be rewrapped at all ]
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 31 Sep 2023 18:13:45 -0500
+"#
+            .as_bytes(),
+        )
+        .unwrap();
+        let lints = linter().lint(&changelog);
+        assert_eq!(lints.len(), 2);
+        assert!(lints.iter().all(|l| l.fix.is_none()));
+        assert_eq!(linter().fix(&mut changelog), vec![]);
+    }
+}
@@ -0,0 +1,770 @@
+//! Three-way merging of changelogs, similar to dpkg-mergechangelogs.
+//!
+//! Entries are matched up by version. An entry that was changed on only one side is taken
+//! from that side. If both sides changed the same entry, the header and footer are merged
+//! separately, and the changes in the body are combined change by change and author section
+//! by author section; a change that was wrapped over several lines is kept together. Only
+//! entries whose header, footer or an individual change were changed in different ways on
+//! both sides, or that were changed on one side and removed on the other, are reported as
+//! conflicts.
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::ChangeLog;
+//! use debian_changelog::merge::merge;
+//!
+//! let base: ChangeLog = r#"foo (1.0-1) unstable; urgency=low
+//!
+//!   * Initial release.
+//!
+//!  -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+//! "#.parse().unwrap();
+//! let ours: ChangeLog = base.to_string().replace("Initial release.", "Initial release. Closes: #1").parse().unwrap();
+//! let theirs: ChangeLog = base.to_string().replace("urgency=low", "urgency=medium").parse().unwrap();
+//!
+//! let merged = merge(&base, &ours, &theirs);
+//! assert!(merged.is_clean());
+//! assert_eq!(merged.to_string(), r#"foo (1.0-1) unstable; urgency=medium
+//!
+//!   * Initial release. Closes: #1
+//!
+//!  -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+//! "#);
+//! ```
+
+use crate::diff::{common_subsequence, occurrence, Key};
+use crate::{ChangeLog, Entry};
+use chrono::{DateTime, FixedOffset};
+use debversion::Version;
+use rowan::ast::AstNode;
+use std::ops::Range;
+
+/// An entry that was changed in incompatible ways on both sides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The version of the entry.
+    pub version: Version,
+    /// The text of the entry in the common ancestor, if it was present there.
+    pub base: Option<String>,
+    /// The text of the entry on our side, if it was not removed.
+    pub ours: Option<String>,
+    /// The text of the entry on their side, if it was not removed.
+    pub theirs: Option<String>,
+}
+
+impl std::fmt::Display for Conflict {
+    /// Format the conflict with conflict markers, like git does.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "<<<<<<< ours")?;
+        write!(f, "{}", self.ours.as_deref().unwrap_or_default())?;
+        writeln!(f, "=======")?;
+        write!(f, "{}", self.theirs.as_deref().unwrap_or_default())?;
+        writeln!(f, ">>>>>>> theirs")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Entry(String),
+    Conflict(Conflict),
+}
+
+/// The result of merging changelogs.
+///
+/// Formatting it with [`std::fmt::Display`] produces the merged text, with conflict markers
+/// around the entries that conflict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    prefix: String,
+    items: Vec<Item>,
+    suffix: String,
+}
+
+impl Merge {
+    /// Returns the entries that could not be merged.
+    pub fn conflicts(&self) -> impl Iterator<Item = &Conflict> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            Item::Conflict(conflict) => Some(conflict),
+            Item::Entry(_) => None,
+        })
+    }
+
+    /// Return whether the merge succeeded without conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts().next().is_none()
+    }
+
+    /// Return the merged changelog, or None if there are conflicts.
+    pub fn changelog(&self) -> Option<ChangeLog> {
+        if !self.is_clean() {
+            return None;
+        }
+        ChangeLog::read_relaxed(self.to_string().as_bytes())
+            .ok()
+            .map(|(changelog, _)| changelog)
+    }
+}
+
+impl std::fmt::Display for Merge {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.prefix)?;
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            match item {
+                Item::Entry(text) => write!(f, "{}", text)?,
+                Item::Conflict(conflict) => write!(f, "{}", conflict)?,
+            }
+        }
+        write!(f, "{}", self.suffix)
+    }
+}
+
+/// The parts of an entry that are merged separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Parts {
+    text: String,
+    header: Option<String>,
+    body: Vec<String>,
+    footer: Option<String>,
+    author: (Option<String>, Option<String>),
+    datetime: Option<DateTime<FixedOffset>>,
+}
+
+impl From<&Entry> for Parts {
+    fn from(entry: &Entry) -> Self {
+        Parts {
+            text: entry.syntax().text().to_string(),
+            header: entry.header_line(),
+            body: entry.body_lines(),
+            footer: entry.footer_line(),
+            author: (entry.maintainer(), entry.email()),
+            datetime: entry.datetime(),
+        }
+    }
+}
+
+impl Parts {
+    fn render(header: &Option<String>, body: &[String], footer: &Option<String>) -> String {
+        let mut text = String::new();
+        if let Some(header) = header {
+            text.push_str(header);
+            text.push_str("\n\n");
+        }
+        for line in body {
+            text.push_str(line);
+            text.push('\n');
+        }
+        if let Some(footer) = footer {
+            if !body.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(footer);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Merge a value that was changed on one or both sides, if they agree.
+fn merge3<T: PartialEq + Clone>(base: Option<&T>, ours: &T, theirs: &T) -> Option<T> {
+    if ours == theirs || base == Some(theirs) {
+        Some(ours.clone())
+    } else if base == Some(ours) {
+        Some(theirs.clone())
+    } else {
+        None
+    }
+}
+
+/// The changes from a common ancestor to one side, as (range in the ancestor, replacement).
+fn hunks<T: PartialEq + Clone>(base: &[T], side: &[T]) -> Vec<(Range<usize>, Vec<T>)> {
+    let mut hunks = vec![];
+    let (mut i, mut j) = (0, 0);
+    // The end of both sequences counts as common, to pick up trailing changes
+    for (next_i, next_j) in common_subsequence(base, side)
+        .into_iter()
+        .chain([(base.len(), side.len())])
+    {
+        if (i, j) != (next_i, next_j) {
+            hunks.push((i..next_i, side[j..next_j].to_vec()));
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    hunks
+}
+
+/// Merge two sequences that were both derived from a common ancestor.
+///
+/// Changes to different parts of the ancestor are combined. When both sides insert items at
+/// the same position, their items come before ours, and items inserted on both sides are
+/// only kept once. Returns None if both sides changed the same part of the ancestor in
+/// different ways.
+fn merge_sequences<T: PartialEq + Clone>(base: &[T], ours: &[T], theirs: &[T]) -> Option<Vec<T>> {
+    // (range, replacement, whether the hunk is ours)
+    let mut all = hunks(base, ours)
+        .into_iter()
+        .map(|(r, v)| (r, v, true))
+        .chain(hunks(base, theirs).into_iter().map(|(r, v)| (r, v, false)))
+        .collect::<Vec<_>>();
+    all.sort_by_key(|(r, _, _)| (r.start, r.end));
+
+    let mut result = vec![];
+    let (mut pos, mut i) = (0, 0);
+    while i < all.len() {
+        let (start, mut end) = (all[i].0.start, all[i].0.end);
+        let mut j = i + 1;
+        // Hunks overlap if they touch the same items, or insert at the same position
+        while j < all.len()
+            && (all[j].0.start < end
+                || (all[j].0.is_empty() && start == end && all[j].0.start == end))
+        {
+            end = end.max(all[j].0.end);
+            j += 1;
+        }
+        let cluster = &all[i..j];
+        result.extend_from_slice(&base[pos..start]);
+        if cluster.len() == 1 {
+            result.extend_from_slice(&cluster[0].1);
+        } else if cluster.iter().all(|(r, _, _)| r.is_empty()) {
+            // Items inserted on both sides are only kept once
+            let theirs = cluster.iter().find(|(_, _, o)| !o).unwrap();
+            let ours = cluster.iter().find(|(_, _, o)| *o).unwrap();
+            result.extend_from_slice(&theirs.1);
+            result.extend(ours.1.iter().filter(|i| !theirs.1.contains(i)).cloned());
+        } else if cluster.len() == 2 && cluster[0].0 == cluster[1].0 && cluster[0].1 == cluster[1].1
+        {
+            result.extend_from_slice(&cluster[0].1);
+        } else {
+            return None;
+        }
+        pos = end;
+        i = j;
+    }
+    result.extend_from_slice(&base[pos..]);
+    Some(result)
+}
+
+/// Identifies a section of a body: its header ("[ Author ]", or None for the changes before
+/// the first header), and how often that header occurred before.
+type SectionKey = (Option<String>, usize);
+
+/// Split the lines of a body into sections, and the sections into the lines of each change.
+///
+/// Blank lines are kept as changes of their own. The lines are kept as they are; they are
+/// only normalised to find the changes and section headers.
+fn sections(body: &[String]) -> Vec<(SectionKey, Vec<Vec<String>>)> {
+    let stripped = body
+        .iter()
+        .map(|l| l.strip_prefix("  ").unwrap_or(l).trim_end_matches(' '))
+        .collect::<Vec<_>>();
+    let mut lines = body.iter();
+    let mut sections: Vec<(SectionKey, Vec<Vec<String>>)> = vec![((None, 0), vec![])];
+    for (is_change, group) in crate::textwrap::group_changes(stripped.into_iter()) {
+        let is_header = !is_change && !group[0].is_empty();
+        let group = lines
+            .by_ref()
+            .take(group.len())
+            .cloned()
+            .collect::<Vec<_>>();
+        if is_header {
+            let header = Some(group[0].clone());
+            let n = sections.iter().filter(|((h, _), _)| h == &header).count();
+            sections.push(((header, n), vec![]));
+        } else {
+            sections.last_mut().unwrap().1.push(group);
+        }
+    }
+    if sections[0].1.is_empty() {
+        sections.remove(0);
+    }
+    sections
+}
+
+/// Merge the bodies of an entry, change by change and section by section.
+///
+/// Returns None if the same change was modified in different ways on both sides, or if a
+/// change was added on one side to a section that was removed on the other side.
+fn merge_body(base: &[String], ours: &[String], theirs: &[String]) -> Option<Vec<String>> {
+    let (base, ours, theirs) = (sections(base), sections(ours), sections(theirs));
+    let keys = |sections: &[(SectionKey, Vec<Vec<String>>)]| {
+        sections.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>()
+    };
+    let merge_section = |key: &SectionKey| {
+        let changes = |sections: &[(SectionKey, Vec<Vec<String>>)]| {
+            sections
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(vec![], |(_, changes)| changes.clone())
+        };
+        merge_sequences(&changes(&base), &changes(&ours), &changes(&theirs))
+    };
+    let is_blank = |line: &String| line.trim().is_empty();
+
+    let order = merge_sequences(&keys(&base), &keys(&ours), &keys(&theirs))?;
+    // A section removed on one side must not have gained changes on the other side
+    for key in keys(&ours).iter().chain(&keys(&theirs)) {
+        if !order.contains(key) && merge_section(key)?.iter().flatten().any(|l| !is_blank(l)) {
+            return None;
+        }
+    }
+    let mut lines = vec![];
+    for key in &order {
+        let changes = merge_section(key)?;
+        // Sections are separated by a blank line
+        if key.0.is_some() && lines.last().is_some_and(|l| !is_blank(l)) {
+            lines.push(String::new());
+        }
+        lines.extend(key.0.clone());
+        lines.extend(changes.into_iter().flatten());
+    }
+    while lines.last().is_some_and(is_blank) {
+        lines.pop();
+    }
+    Some(lines)
+}
+
+/// Merge an entry that is present on at least one side, returning None on a conflict.
+fn merge_entry(
+    base: Option<&Parts>,
+    ours: Option<&Parts>,
+    theirs: Option<&Parts>,
+) -> Option<Option<String>> {
+    let text = |p: Option<&Parts>| p.map(|p| p.text.clone());
+    if let Some(text) = merge3(Some(&text(base)), &text(ours), &text(theirs)) {
+        return Some(text);
+    }
+    let (ours, theirs) = (ours?, theirs?);
+    let header = merge3(base.map(|p| &p.header), &ours.header, &theirs.header)?;
+    let body = match merge3(base.map(|p| &p.body), &ours.body, &theirs.body) {
+        Some(body) => body,
+        None => merge_body(base.map_or(&[][..], |p| &p.body), &ours.body, &theirs.body)?,
+    };
+    let footer = merge3(base.map(|p| &p.footer), &ours.footer, &theirs.footer).or_else(|| {
+        // If the same person touched the entry on both sides, keep the most recent timestamp.
+        if ours.author != theirs.author {
+            return None;
+        }
+        match (ours.datetime, theirs.datetime) {
+            (Some(o), Some(t)) if o >= t => Some(ours.footer.clone()),
+            (Some(_), Some(_)) => Some(theirs.footer.clone()),
+            _ => None,
+        }
+    })?;
+    Some(Some(Parts::render(&header, &body, &footer)))
+}
+
+/// The text before the first entry and after the last entry.
+fn surroundings(changelog: &ChangeLog) -> (String, String) {
+    let text = changelog.syntax().text().to_string();
+    let entries = changelog.entries().collect::<Vec<_>>();
+    match (entries.first(), entries.last()) {
+        (Some(first), Some(last)) => (
+            text[..usize::from(first.syntax().text_range().start())].to_string(),
+            text[usize::from(last.syntax().text_range().end())..].to_string(),
+        ),
+        _ => (text, String::new()),
+    }
+}
+
+/// Merge two changelogs that were both derived from a common ancestor.
+///
+/// Text outside of entries, such as comments at the end of the file, is taken from our side.
+/// Entries without a parseable version are kept from our side, in their original position.
+/// If a version appears more than once, its entries are matched up in the order in which
+/// they appear.
+pub fn merge(base: &ChangeLog, ours: &ChangeLog, theirs: &ChangeLog) -> Merge {
+    let versioned = |changelog: &ChangeLog| {
+        let mut ret: Vec<(Key, Parts)> = vec![];
+        for entry in changelog.entries() {
+            if let Some(version) = entry.version() {
+                let key = occurrence(ret.iter().map(|(k, _)| k), version);
+                ret.push((key, Parts::from(&entry)));
+            }
+        }
+        ret
+    };
+    let (base_entries, our_entries, their_entries) =
+        (versioned(base), versioned(ours), versioned(theirs));
+
+    let mut versions = base_entries
+        .iter()
+        .chain(our_entries.iter())
+        .chain(their_entries.iter())
+        .map(|(k, _)| k.clone())
+        .collect::<Vec<_>>();
+    // Newest version first; repeated versions in the order in which they appear
+    versions.sort_by(|(a, i), (b, j)| b.cmp(a).then(i.cmp(j)));
+    versions.dedup();
+
+    fn find<'a>(entries: &'a [(Key, Parts)], key: &Key) -> Option<&'a Parts> {
+        entries.iter().find(|(k, _)| k == key).map(|(_, p)| p)
+    }
+
+    // Entries without a version on our side, keyed by the entry above them.
+    let mut unversioned: Vec<(Option<Key>, String)> = vec![];
+    let mut seen: Vec<Key> = vec![];
+    for entry in ours.entries() {
+        match entry.version() {
+            Some(version) => seen.push(occurrence(seen.iter(), version)),
+            None => unversioned.push((seen.last().cloned(), entry.syntax().text().to_string())),
+        }
+    }
+    let take_unversioned = |items: &mut Vec<Item>, above: Option<&Key>| {
+        for (_, text) in unversioned.iter().filter(|(v, _)| v.as_ref() == above) {
+            items.push(Item::Entry(text.clone()));
+        }
+    };
+
+    let mut items = vec![];
+    take_unversioned(&mut items, None);
+    for key in versions {
+        let (b, o, t) = (
+            find(&base_entries, &key),
+            find(&our_entries, &key),
+            find(&their_entries, &key),
+        );
+        match merge_entry(b, o, t) {
+            Some(Some(text)) => items.push(Item::Entry(text)),
+            Some(None) => {}
+            None => items.push(Item::Conflict(Conflict {
+                version: key.0.clone(),
+                base: b.map(|p| p.text.clone()),
+                ours: o.map(|p| p.text.clone()),
+                theirs: t.map(|p| p.text.clone()),
+            })),
+        }
+        take_unversioned(&mut items, Some(&key));
+    }
+
+    let (prefix, suffix) = surroundings(ours);
+    Merge {
+        prefix,
+        items,
+        suffix,
+    }
+}
+
+/// Merge changelog files, in the manner of a git merge driver.
+///
+/// The result is written to `ours`, with conflict markers around entries that conflict.
+/// Returns whether the merge was clean. The `debian-changelog-merge-driver` binary calls
+/// this with its arguments.
+pub fn merge_files(
+    base: impl AsRef<std::path::Path>,
+    ours: impl AsRef<std::path::Path>,
+    theirs: impl AsRef<std::path::Path>,
+) -> Result<bool, crate::Error> {
+    let read = |path: &std::path::Path| -> Result<ChangeLog, crate::Error> {
+        Ok(ChangeLog::read_relaxed(std::fs::File::open(path)?)?.0)
+    };
+    let merged = merge(
+        &read(base.as_ref())?,
+        &read(ours.as_ref())?,
+        &read(theirs.as_ref())?,
+    );
+    std::fs::write(ours.as_ref(), merged.to_string())?;
+    Ok(merged.is_clean())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"foo (1.0-2) unstable; urgency=low
+
+  * Fix a bug.
+
+ -- Joe Example <joe@example.com>  Tue, 05 Sep 2023 18:13:45 -0500
+
+foo (1.0-1) unstable; urgency=low
+
+  * Initial release.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+
+    fn entry(version: &str, changes: &[&str], date: &str) -> String {
+        format!(
+            "foo ({}) unstable; urgency=low\n\n{}\n\n -- Joe Example <joe@example.com>  {}\n",
+            version,
+            changes
+                .iter()
+                .map(|c| format!("  * {}", c))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            date
+        )
+    }
+
+    fn run(base: &str, ours: &str, theirs: &str) -> Merge {
+        merge(
+            &base.parse().unwrap(),
+            &ours.parse().unwrap(),
+            &theirs.parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_new_entries_on_both_sides() {
+        let ours = format!(
+            "{}\n{}",
+            entry("1.0-3", &["Ours."], "Wed, 06 Sep 2023 18:13:45 -0500"),
+            BASE
+        );
+        let theirs = format!(
+            "{}\n{}",
+            entry("1.1-1", &["Theirs."], "Thu, 07 Sep 2023 18:13:45 -0500"),
+            BASE
+        );
+        let merged = run(BASE, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.to_string(),
+            format!(
+                "{}\n{}\n{}",
+                entry("1.1-1", &["Theirs."], "Thu, 07 Sep 2023 18:13:45 -0500"),
+                entry("1.0-3", &["Ours."], "Wed, 06 Sep 2023 18:13:45 -0500"),
+                BASE
+            )
+        );
+        let versions = merged
+            .changelog()
+            .unwrap()
+            .entries()
+            .map(|e| e.version().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec!["1.1-1", "1.0-3", "1.0-2", "1.0-1"]);
+    }
+
+    #[test]
+    fn test_union_of_changes() {
+        let base = entry("1.0-3", &["Start."], "Wed, 06 Sep 2023 18:13:45 -0500");
+        let ours = entry(
+            "1.0-3",
+            &["Start.", "Ours."],
+            "Wed, 06 Sep 2023 19:00:00 -0500",
+        );
+        let theirs = entry(
+            "1.0-3",
+            &["Start.", "Theirs."],
+            "Wed, 06 Sep 2023 20:00:00 -0500",
+        );
+        let merged = run(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.to_string(),
+            entry(
+                "1.0-3",
+                &["Start.", "Theirs.", "Ours."],
+                "Wed, 06 Sep 2023 20:00:00 -0500"
+            )
+        );
+    }
+
+    #[test]
+    fn test_removed_change() {
+        let base = entry("1.0-3", &["A.", "B."], "Wed, 06 Sep 2023 18:13:45 -0500");
+        let ours = entry(
+            "1.0-3",
+            &["A.", "B.", "C."],
+            "Wed, 06 Sep 2023 18:13:45 -0500",
+        );
+        let theirs = entry("1.0-3", &["B.", "D."], "Wed, 06 Sep 2023 18:13:45 -0500");
+        assert_eq!(
+            run(&base, &ours, &theirs).to_string(),
+            entry(
+                "1.0-3",
+                &["B.", "D.", "C."],
+                "Wed, 06 Sep 2023 18:13:45 -0500"
+            )
+        );
+    }
+
+    #[test]
+    fn test_wrapped_changes() {
+        let date = "Wed, 06 Sep 2023 18:13:45 -0500";
+        let base = entry("1.0-3", &["A."], date);
+        let ours = entry(
+            "1.0-3",
+            &[
+                "A.",
+                "Fix the frobnicator so that it no longer crashes\n    on startup.",
+            ],
+            date,
+        );
+        let theirs = entry(
+            "1.0-3",
+            &[
+                "A.",
+                "Update the documentation so that it no longer crashes\n    on startup.",
+            ],
+            date,
+        );
+        let merged = run(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.to_string(),
+            entry(
+                "1.0-3",
+                &[
+                    "A.",
+                    "Update the documentation so that it no longer crashes\n    on startup.",
+                    "Fix the frobnicator so that it no longer crashes\n    on startup.",
+                ],
+                date
+            )
+        );
+    }
+
+    #[test]
+    fn test_new_sections_on_both_sides() {
+        let date = "Wed, 06 Sep 2023 18:13:45 -0500";
+        let base = entry("1.0-3", &["A."], date);
+        let ours = entry("1.0-3", &["A.\n\n  [ Joe ]", "B."], date);
+        let theirs = entry("1.0-3", &["A.\n\n  [ Bob ]", "C."], date);
+        let merged = run(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.to_string(),
+            entry("1.0-3", &["A.\n\n  [ Bob ]", "C.\n\n  [ Joe ]", "B."], date)
+        );
+
+        // Changes added to the same new section on both sides end up in one section
+        let ours = entry("1.0-3", &["A.\n\n  [ Joe ]", "B."], date);
+        let theirs = entry("1.0-3", &["A.\n\n  [ Joe ]", "C."], date);
+        assert_eq!(
+            run(&base, &ours, &theirs).to_string(),
+            entry("1.0-3", &["A.\n\n  [ Joe ]", "C.", "B."], date)
+        );
+    }
+
+    #[test]
+    fn test_untouched_lines_are_kept() {
+        let base = r#"foo (1.0-3) unstable; urgency=low
+
+  * A.
+   * Odd indent kept.  
+
+ -- Joe Example <joe@example.com>  Wed, 06 Sep 2023 18:13:45 -0500
+"#;
+        let ours = base.replace("  * A.\n", "  * A.\n  * Ours.\n");
+        let theirs = base.replace("  * A.\n", "  * A.\n  * Theirs.\n");
+        let merged = run(base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.to_string(),
+            base.replace("  * A.\n", "  * A.\n  * Theirs.\n  * Ours.\n")
+        );
+    }
+
+    #[test]
+    fn test_change_modified_on_both_sides() {
+        let date = "Wed, 06 Sep 2023 18:13:45 -0500";
+        let base = entry("1.0-3", &["A.", "B."], date);
+        let ours = entry("1.0-3", &["A. Closes: #1", "B.", "C."], date);
+        let theirs = entry("1.0-3", &["A. Closes: #2", "B."], date);
+        let merged = run(&base, &ours, &theirs);
+        assert!(!merged.is_clean());
+        assert_eq!(merged.conflicts().count(), 1);
+
+        // A change added to a section that was removed on the other side
+        let base = entry("1.0-3", &["A.\n\n  [ Joe ]", "B."], date);
+        let ours = entry("1.0-3", &["A.\n\n  [ Joe ]", "B.", "C."], date);
+        let theirs = entry("1.0-3", &["A."], date);
+        assert!(!run(&base, &ours, &theirs).is_clean());
+    }
+
+    #[test]
+    fn test_conflict() {
+        let ours = BASE.replacen("unstable", "experimental", 1);
+        let theirs = BASE.replacen("unstable", "bookworm", 1);
+        let merged = run(BASE, &ours, &theirs);
+        assert!(!merged.is_clean());
+        assert!(merged.changelog().is_none());
+        let conflicts = merged.conflicts().collect::<Vec<_>>();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].version, "1.0-2".parse().unwrap());
+        assert_eq!(
+            merged.to_string(),
+            format!(
+                "<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n\n{}",
+                entry("1.0-2", &["Fix a bug."], "Tue, 05 Sep 2023 18:13:45 -0500")
+                    .replace("unstable", "experimental"),
+                entry("1.0-2", &["Fix a bug."], "Tue, 05 Sep 2023 18:13:45 -0500")
+                    .replace("unstable", "bookworm"),
+                entry(
+                    "1.0-1",
+                    &["Initial release."],
+                    "Mon, 04 Sep 2023 18:13:45 -0500"
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_removed_and_modified() {
+        let ours = entry(
+            "1.0-1",
+            &["Initial release."],
+            "Mon, 04 Sep 2023 18:13:45 -0500",
+        );
+        let theirs = BASE.replace("Fix a bug.", "Fix two bugs.");
+        let merged = run(BASE, &ours, &theirs);
+        let conflicts = merged.conflicts().collect::<Vec<_>>();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].ours, None);
+        assert!(merged
+            .to_string()
+            .starts_with("<<<<<<< ours\n=======\nfoo (1.0-2)"));
+    }
+
+    #[test]
+    fn test_repeated_version() {
+        let base = r#"foo (1.0-1) unstable; urgency=low
+
+  * Second upload.
+
+ -- Joe Example <joe@example.com>  Tue, 05 Sep 2023 18:13:45 -0500
+
+foo (1.0-1) unstable; urgency=low
+
+  * First upload.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+        let merged = run(base, base, base);
+        assert!(merged.is_clean());
+        assert_eq!(merged.to_string(), base);
+
+        let ours = base.replace(
+            "  * First upload.
+",
+            "  * First upload.
+  * Ours.
+",
+        );
+        let merged = run(base, &ours, base);
+        assert!(merged.is_clean());
+        assert_eq!(merged.to_string(), ours);
+    }
+
+    #[test]
+    fn test_merge_files() {
+        let dir = std::env::temp_dir().join(format!("merge-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ours = format!(
+            "{}\n{}",
+            entry("1.0-3", &["Ours."], "Wed, 06 Sep 2023 18:13:45 -0500"),
+            BASE
+        );
+        std::fs::write(dir.join("base"), BASE).unwrap();
+        std::fs::write(dir.join("ours"), &ours).unwrap();
+        std::fs::write(dir.join("theirs"), BASE).unwrap();
+        assert!(merge_files(dir.join("base"), dir.join("ours"), dir.join("theirs")).unwrap());
+        assert_eq!(std::fs::read_to_string(dir.join("ours")).unwrap(), ours);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
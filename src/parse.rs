@@ -1,3 +1,4 @@
+use crate::diagnostic::{text_range, Diagnostic, DiagnosticKind, Severity};
 use crate::lex::lex;
 use crate::SyntaxKind;
 use crate::SyntaxKind::*;
@@ -6,24 +7,59 @@ use debversion::Version;
 use rowan::ast::AstNode;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+/// The urgency of an upload.
+///
+/// The known levels are ordered from `Low` to `Emergency`, as in dpkg;
+/// any other value is kept (in lowercase) as `Other`, and sorts below
+/// the known levels.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum Urgency {
     #[default]
     Low,
     Medium,
     High,
-    Emergency,
     Critical,
+    Emergency,
+    Other(String),
+}
+
+impl Urgency {
+    pub(crate) fn rank(&self) -> u8 {
+        match self {
+            Urgency::Other(_) => 0,
+            Urgency::Low => 1,
+            Urgency::Medium => 2,
+            Urgency::High => 3,
+            Urgency::Critical => 4,
+            Urgency::Emergency => 5,
+        }
+    }
+}
+
+impl Ord for Urgency {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (Urgency::Other(a), Urgency::Other(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Urgency {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
-impl ToString for Urgency {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Urgency {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Urgency::Low => "low".to_string(),
-            Urgency::Medium => "medium".to_string(),
-            Urgency::High => "high".to_string(),
-            Urgency::Emergency => "emergency".to_string(),
-            Urgency::Critical => "critical".to_string(),
+            Urgency::Low => f.write_str("low"),
+            Urgency::Medium => f.write_str("medium"),
+            Urgency::High => f.write_str("high"),
+            Urgency::Emergency => f.write_str("emergency"),
+            Urgency::Critical => f.write_str("critical"),
+            Urgency::Other(urgency) => f.write_str(urgency),
         }
     }
 }
@@ -38,8 +74,85 @@ impl FromStr for Urgency {
             "high" => Ok(Urgency::High),
             "emergency" => Ok(Urgency::Emergency),
             "critical" => Ok(Urgency::Critical),
-            _ => Err(ParseError(vec![format!("invalid urgency: {}", s)])),
+            other if lazy_regex::regex_is_match!(r"^[a-z][a-z0-9-]*$", other) => {
+                Ok(Urgency::Other(other.to_string()))
+            }
+            _ => Err(ParseError(vec![Diagnostic::new(
+                DiagnosticKind::InvalidUrgency(s.to_string()),
+                Severity::Error,
+                text_range(0, s.len()),
+                s,
+            )])),
+        }
+    }
+}
+
+/// An urgency as written in a changelog header, e.g. "MEDIUM (was low)".
+///
+/// Unlike `Urgency`, this preserves the case of the value and the optional
+/// comment, so that it can be written back unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UrgencyValue {
+    value: String,
+    comment: Option<String>,
+}
+
+impl UrgencyValue {
+    /// Create a new urgency value, with an optional comment.
+    pub fn new(urgency: Urgency, comment: Option<&str>) -> Self {
+        Self {
+            value: urgency.to_string(),
+            comment: comment.map(|c| c.to_string()),
+        }
+    }
+
+    /// The urgency level.
+    pub fn urgency(&self) -> Urgency {
+        self.value
+            .parse()
+            .unwrap_or_else(|_| Urgency::Other(self.value.to_lowercase()))
+    }
+
+    /// The value as written, e.g. "MEDIUM".
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// The comment, without parentheses.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+}
+
+impl From<Urgency> for UrgencyValue {
+    fn from(urgency: Urgency) -> Self {
+        Self::new(urgency, None)
+    }
+}
+
+impl std::fmt::Display for UrgencyValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.value)?;
+        if let Some(comment) = self.comment.as_ref() {
+            write!(f, " ({})", comment)?;
         }
+        Ok(())
+    }
+}
+
+impl FromStr for UrgencyValue {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, comment) = match lazy_regex::regex_captures!(r"^(\S+)\s+\((.*)\)$", s) {
+            Some((_, value, comment)) => (value, Some(comment.to_string())),
+            None => (s, None),
+        };
+        value.parse::<Urgency>()?;
+        Ok(Self {
+            value: value.to_string(),
+            comment,
+        })
     }
 }
 
@@ -67,7 +180,14 @@ impl From<std::io::Error> for Error {
 impl std::error::Error for Error {}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct ParseError(Vec<String>);
+pub struct ParseError(Vec<Diagnostic>);
+
+impl ParseError {
+    /// Returns the diagnostics that caused parsing to fail.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.0
+    }
+}
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -115,32 +235,56 @@ use rowan::GreenNodeBuilder;
 #[derive(Debug)]
 struct Parse {
     green_node: GreenNode,
-    #[allow(unused)]
-    errors: Vec<String>,
+    errors: Vec<Diagnostic>,
 }
 
 fn parse(text: &str) -> Parse {
-    struct Parser {
+    struct Parser<'a> {
+        /// the original text, used to locate errors.
+        text: &'a str,
         /// input tokens, including whitespace,
         /// in *reverse* order.
         tokens: Vec<(SyntaxKind, String)>,
+        /// byte offset of the first unprocessed token.
+        offset: usize,
         /// the in-progress tree.
         builder: GreenNodeBuilder<'static>,
         /// the list of syntax errors we've accumulated
         /// so far.
-        errors: Vec<String>,
+        errors: Vec<Diagnostic>,
     }
 
-    impl Parser {
-        fn error(&mut self, msg: String) {
+    impl Parser<'_> {
+        fn error(&mut self, kind: DiagnosticKind) {
+            let start = self.offset;
             self.builder.start_node(ERROR.into());
             if self.current().is_some() {
                 self.bump();
             }
-            self.errors.push(msg);
+            self.errors.push(Diagnostic::new(
+                kind,
+                Severity::Error,
+                text_range(start, self.offset - start),
+                self.text,
+            ));
             self.builder.finish_node();
         }
 
+        /// Record a problem with the value of the current token, without
+        /// marking it as an error in the tree.
+        fn warning(&mut self, kind: DiagnosticKind, offset: usize, len: usize) {
+            self.errors.push(Diagnostic::new(
+                kind,
+                Severity::Warning,
+                text_range(self.offset + offset, len),
+                self.text,
+            ));
+        }
+
+        fn current_text(&self) -> Option<&str> {
+            self.tokens.last().map(|(_, text)| text.as_str())
+        }
+
         fn parse_entry_header(&mut self) {
             self.builder.start_node(ENTRY_HEADER.into());
             self.expect(IDENTIFIER);
@@ -153,6 +297,14 @@ fn parse(text: &str) -> Parse {
                 return;
             }
 
+            if self.current() == Some(VERSION) {
+                let text = self.current_text().unwrap();
+                let version = &text[1..text.len() - 1];
+                if version.parse::<Version>().is_err() {
+                    let kind = DiagnosticKind::InvalidVersion(version.to_string());
+                    self.warning(kind, 1, version.len());
+                }
+            }
             self.expect(VERSION);
 
             self.builder.start_node(DISTRIBUTIONS.into());
@@ -161,25 +313,19 @@ fn parse(text: &str) -> Parse {
 
                 match self.current() {
                     Some(IDENTIFIER) => self.bump(),
-                    Some(NEWLINE) => {
-                        self.bump();
-                        self.builder.finish_node();
-                        self.builder.finish_node();
-                        return;
-                    }
-                    Some(SEMICOLON) => {
+                    Some(NEWLINE) | Some(SEMICOLON) => {
                         break;
                     }
                     _ => {
-                        self.error("expected distribution or semicolon".to_string());
+                        self.error(DiagnosticKind::ExpectedDistribution);
                         break;
                     }
                 }
             }
             self.builder.finish_node();
 
-            self.builder.start_node(METADATA.into());
             if self.current() == Some(SEMICOLON) {
+                self.builder.start_node(METADATA.into());
                 self.bump();
                 loop {
                     self.skip_ws();
@@ -189,12 +335,13 @@ fn parse(text: &str) -> Parse {
                     }
 
                     self.builder.start_node(METADATA_ENTRY.into());
+                    let key = self.current_text().map(|s| s.to_string());
                     if self.current() == Some(IDENTIFIER) {
                         self.builder.start_node(METADATA_KEY.into());
                         self.bump();
                         self.builder.finish_node();
                     } else {
-                        self.error("expected metadata key".to_string());
+                        self.error(DiagnosticKind::ExpectedMetadataKey);
                         self.builder.finish_node();
                         break;
                     }
@@ -202,27 +349,45 @@ fn parse(text: &str) -> Parse {
                     if self.current() == Some(EQUALS) {
                         self.bump();
                     } else {
-                        self.error("expected equals".to_string());
+                        self.error(DiagnosticKind::Expected {
+                            expected: EQUALS,
+                            found: self.current(),
+                        });
                         self.builder.finish_node();
                         break;
                     }
 
                     if self.current() == Some(IDENTIFIER) {
+                        let value = self.current_text().unwrap();
+                        if key.as_deref() == Some("urgency") && value.parse::<Urgency>().is_err() {
+                            let kind = DiagnosticKind::InvalidUrgency(value.to_string());
+                            self.warning(kind, 0, value.len());
+                        }
                         self.builder.start_node(METADATA_VALUE.into());
                         self.bump();
+                        if self.current() == Some(WHITESPACE) && self.next() == Some(COMMENT) {
+                            self.bump();
+                        }
+                        if self.current() == Some(COMMENT) {
+                            self.bump();
+                        }
                         self.builder.finish_node();
                     } else {
-                        self.error("expected metadata value".to_string());
+                        self.error(DiagnosticKind::ExpectedMetadataValue);
                         self.builder.finish_node();
                         break;
                     }
                     self.builder.finish_node();
+
+                    self.skip_ws();
+                    if self.current() == Some(COMMA) {
+                        self.bump();
+                    }
                 }
-            } else if self.current() == Some(NEWLINE) {
-            } else {
-                self.error("expected semicolon or newline".to_string());
+                self.builder.finish_node();
+            } else if self.current() != Some(NEWLINE) {
+                self.error(DiagnosticKind::ExpectedSemicolonOrNewline);
             }
-            self.builder.finish_node();
 
             self.expect(NEWLINE);
             self.builder.finish_node();
@@ -238,7 +403,7 @@ fn parse(text: &str) -> Parse {
                     .map(|(kind, token)| (kind, token.as_str()))
                 {
                     None => {
-                        self.error("unexpected end of file".to_string());
+                        self.error(DiagnosticKind::UnexpectedEndOfFile);
                         break;
                     }
                     // empty line
@@ -273,7 +438,7 @@ fn parse(text: &str) -> Parse {
                 }
                 Some(NEWLINE) => {}
                 _ => {
-                    self.error("expected detail".to_string());
+                    self.error(DiagnosticKind::ExpectedDetail);
                 }
             }
 
@@ -285,11 +450,14 @@ fn parse(text: &str) -> Parse {
             self.builder.start_node(ENTRY_FOOTER.into());
 
             if self.current() != Some(INDENT) {
-                self.error("expected indent".to_string());
+                self.error(DiagnosticKind::Expected {
+                    expected: INDENT,
+                    found: self.current(),
+                });
             } else {
                 let dashes = &self.tokens.last().unwrap().1;
                 if dashes != " -- " {
-                    self.error("expected --".to_string());
+                    self.error(DiagnosticKind::ExpectedFooterDashes);
                 } else {
                     self.bump();
                 }
@@ -314,13 +482,16 @@ fn parse(text: &str) -> Parse {
             if self.tokens.last().map(|(k, t)| (*k, t.as_str())) == Some((WHITESPACE, "  ")) {
                 self.bump();
             } else if self.current() == Some(WHITESPACE) {
-                self.error("expected two spaces".to_string());
+                self.error(DiagnosticKind::ExpectedTwoSpaces);
             } else if self.current() == Some(NEWLINE) {
                 self.bump();
                 self.builder.finish_node();
                 return;
             } else {
-                self.error(format!("expected whitespace, got {:?}", self.current()));
+                self.error(DiagnosticKind::Expected {
+                    expected: WHITESPACE,
+                    found: self.current(),
+                });
             }
 
             self.builder.start_node(TIMESTAMP.into());
@@ -354,7 +525,7 @@ fn parse(text: &str) -> Parse {
                         self.parse_entry();
                     }
                     t => {
-                        self.error(format!("unexpected token {:?}", t));
+                        self.error(DiagnosticKind::UnexpectedToken(t));
                         break;
                     }
                 }
@@ -371,6 +542,7 @@ fn parse(text: &str) -> Parse {
         /// Advance one token, adding it to the current branch of the tree builder.
         fn bump(&mut self) {
             let (kind, text) = self.tokens.pop().unwrap();
+            self.offset += text.len();
             self.builder.token(kind.into(), text.as_str());
         }
         /// Peek at the first unprocessed token
@@ -380,13 +552,18 @@ fn parse(text: &str) -> Parse {
 
         fn next(&self) -> Option<SyntaxKind> {
             self.tokens
-                .get(self.tokens.len() - 2)
+                .len()
+                .checked_sub(2)
+                .and_then(|i| self.tokens.get(i))
                 .map(|(kind, _)| *kind)
         }
 
         fn expect(&mut self, expected: SyntaxKind) {
             if self.current() != Some(expected) {
-                self.error(format!("expected {:?}, got {:?}", expected, self.current()));
+                self.error(DiagnosticKind::Expected {
+                    expected,
+                    found: self.current(),
+                });
             } else {
                 self.bump();
             }
@@ -401,7 +578,9 @@ fn parse(text: &str) -> Parse {
     let mut tokens = lex(text);
     tokens.reverse();
     Parser {
+        text,
         tokens,
+        offset: 0,
         builder: GreenNodeBuilder::new(),
         errors: Vec::new(),
     }
@@ -413,12 +592,9 @@ fn parse(text: &str) -> Parse {
 /// It is also immutable, like a GreenNode,
 /// but it contains parent pointers, offsets, and
 /// has identity semantics.
-
-type SyntaxNode = rowan::SyntaxNode<Lang>;
-#[allow(unused)]
-type SyntaxToken = rowan::SyntaxToken<Lang>;
-#[allow(unused)]
-type SyntaxElement = rowan::NodeOrToken<SyntaxNode, SyntaxToken>;
+pub(crate) type SyntaxNode = rowan::SyntaxNode<Lang>;
+pub(crate) type SyntaxToken = rowan::SyntaxToken<Lang>;
+pub(crate) type SyntaxElement = rowan::NodeOrToken<SyntaxNode, SyntaxToken>;
 
 impl Parse {
     fn syntax(&self) -> SyntaxNode {
@@ -430,6 +606,54 @@ impl Parse {
     }
 }
 
+/// Create a new, detached token that can be spliced into a mutable tree.
+pub(crate) fn new_token(kind: SyntaxKind, text: &str) -> SyntaxElement {
+    let mut builder = GreenNodeBuilder::new();
+    builder.start_node(ROOT.into());
+    builder.token(kind.into(), text);
+    builder.finish_node();
+    let token = SyntaxNode::new_root(builder.finish())
+        .clone_for_update()
+        .first_token()
+        .unwrap();
+    token.detach();
+    token.into()
+}
+
+/// Create a new, detached node of the given kind that can be spliced into a mutable tree.
+pub(crate) fn new_node(
+    kind: SyntaxKind,
+    build: impl FnOnce(&mut GreenNodeBuilder),
+) -> SyntaxElement {
+    let mut builder = GreenNodeBuilder::new();
+    builder.start_node(kind.into());
+    build(&mut builder);
+    builder.finish_node();
+    SyntaxNode::new_root(builder.finish())
+        .clone_for_update()
+        .into()
+}
+
+/// Add text to a footer, splitting it up into TEXT and WHITESPACE tokens like the lexer does.
+fn build_text(builder: &mut GreenNodeBuilder, text: &str) {
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let is_ws = c == ' ' || c == '\t';
+        let end = rest
+            .find(|c: char| (c == ' ' || c == '\t') != is_ws)
+            .unwrap_or(rest.len());
+        builder.token(if is_ws { WHITESPACE } else { TEXT }.into(), &rest[..end]);
+        rest = &rest[end..];
+    }
+}
+
+/// Find the index of the first direct child of one of the given kinds.
+fn child_index(node: &SyntaxNode, kinds: &[SyntaxKind]) -> Option<usize> {
+    node.children_with_tokens()
+        .find(|it| kinds.contains(&it.kind()))
+        .map(|it| it.index())
+}
+
 macro_rules! ast_node {
     ($ast:ident, $kind:ident) => {
         #[derive(PartialEq, Eq, Hash)]
@@ -456,9 +680,9 @@ macro_rules! ast_node {
             }
         }
 
-        impl ToString for $ast {
-            fn to_string(&self) -> String {
-                self.0.text().to_string()
+        impl std::fmt::Display for $ast {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}", self.0.text())
             }
         }
     };
@@ -476,6 +700,29 @@ ast_node!(MetadataKey, METADATA_KEY);
 ast_node!(MetadataValue, METADATA_VALUE);
 
 impl MetadataEntry {
+    fn build(builder: &mut GreenNodeBuilder, key: &str, value: &str) {
+        builder.start_node(METADATA_KEY.into());
+        builder.token(IDENTIFIER.into(), key);
+        builder.finish_node(); // METADATA_KEY
+        builder.token(EQUALS.into(), "=");
+        builder.start_node(METADATA_VALUE.into());
+        Self::build_value(builder, value);
+        builder.finish_node(); // METADATA_VALUE
+    }
+
+    /// Add the tokens for a value, splitting off a trailing comment like the lexer does.
+    fn build_value(builder: &mut GreenNodeBuilder, value: &str) {
+        if let Some((_, value, ws, comment)) =
+            lazy_regex::regex_captures!(r"^(\S+)(\s+)(\(.*\))$", value)
+        {
+            builder.token(IDENTIFIER.into(), value);
+            builder.token(WHITESPACE.into(), ws);
+            builder.token(COMMENT.into(), comment);
+        } else {
+            builder.token(IDENTIFIER.into(), value);
+        }
+    }
+
     pub fn key(&self) -> Option<String> {
         self.0
             .children()
@@ -489,12 +736,33 @@ impl MetadataEntry {
             .find_map(MetadataValue::cast)
             .map(|k| k.to_string())
     }
+
+    /// Set the value of this entry, leaving the key untouched.
+    pub fn set_value(&mut self, value: &str) {
+        if let Some(node) = self.0.children().find(|it| it.kind() == METADATA_VALUE) {
+            let value = new_node(METADATA_VALUE, |builder| Self::build_value(builder, value));
+            self.0
+                .splice_children(node.index()..node.index() + 1, vec![value]);
+            return;
+        }
+        // Recover from a partial entry, e.g. "urgency" or "urgency="
+        let mut elements = vec![];
+        if !self.0.children_with_tokens().any(|it| it.kind() == EQUALS) {
+            elements.push(new_token(EQUALS, "="));
+        }
+        elements.push(new_node(METADATA_VALUE, |builder| {
+            Self::build_value(builder, value)
+        }));
+        let index = self.0.children_with_tokens().count();
+        self.0.splice_children(index..index, elements);
+    }
 }
 
 pub struct EntryBuilder {
     root: SyntaxNode,
     package: Option<String>,
     version: Option<Version>,
+    previous_version: Option<Version>,
     distributions: Option<Vec<String>>,
     urgency: Option<Urgency>,
     maintainer: Option<(String, String)>,
@@ -515,6 +783,17 @@ impl EntryBuilder {
         self
     }
 
+    /// Set the version by applying a bump strategy to the version of the previous entry.
+    ///
+    /// This has no effect if there is no previous entry with a valid version.
+    #[must_use]
+    pub fn bump(mut self, bump: crate::version::VersionBump) -> Self {
+        if let Some(previous) = self.previous_version.as_ref() {
+            self.version = Some(bump.next_version(previous));
+        }
+        self
+    }
+
     #[must_use]
     pub fn distributions(mut self, distributions: Vec<String>) -> Self {
         self.distributions = Some(distributions);
@@ -608,8 +887,8 @@ impl EntryBuilder {
             );
         }
         if let Some(distributions) = self.distributions.as_ref() {
-            builder.token(WHITESPACE.into(), " ");
             builder.start_node(DISTRIBUTIONS.into());
+            builder.token(WHITESPACE.into(), " ");
             let mut it = distributions.iter().peekable();
             while it.peek().is_some() {
                 builder.token(IDENTIFIER.into(), it.next().unwrap());
@@ -621,19 +900,17 @@ impl EntryBuilder {
         }
         let mut metadata = self.metadata().peekable();
         if metadata.peek().is_some() {
+            builder.start_node(METADATA.into());
             builder.token(SEMICOLON.into(), ";");
             builder.token(WHITESPACE.into(), " ");
-            builder.start_node(METADATA.into());
-            for (key, value) in metadata {
+            while let Some((key, value)) = metadata.next() {
                 builder.start_node(METADATA_ENTRY.into());
-                builder.start_node(METADATA_KEY.into());
-                builder.token(IDENTIFIER.into(), key.as_str());
-                builder.finish_node(); // METADATA_KEY
-                builder.token(EQUALS.into(), "=");
-                builder.start_node(METADATA_VALUE.into());
-                builder.token(METADATA_VALUE.into(), value.as_str());
-                builder.finish_node(); // METADATA_VALUE
+                MetadataEntry::build(&mut builder, key.as_str(), value.as_str());
                 builder.finish_node(); // METADATA_ENTRY
+                if metadata.peek().is_some() {
+                    builder.token(COMMA.into(), ",");
+                    builder.token(WHITESPACE.into(), " ");
+                }
             }
             builder.finish_node(); // METADATA
         }
@@ -660,13 +937,7 @@ impl EntryBuilder {
         builder.token(INDENT.into(), " -- ");
         if let Some(maintainer) = self.maintainer.as_ref() {
             builder.start_node(MAINTAINER.into());
-            let mut it = maintainer.0.split(' ').peekable();
-            while let Some(p) = it.next() {
-                builder.token(TEXT.into(), p);
-                if it.peek().is_some() {
-                    builder.token(WHITESPACE.into(), " ");
-                }
-            }
+            build_text(&mut builder, maintainer.0.as_str());
             builder.finish_node(); // MAINTAINER
         }
 
@@ -679,14 +950,10 @@ impl EntryBuilder {
             builder.token(WHITESPACE.into(), "  ");
 
             builder.start_node(TIMESTAMP.into());
-            let ts = timestamp.format("%a, %d %b %Y %H:%M:%S %z").to_string();
-            let mut it = ts.split(' ').peekable();
-            while let Some(p) = it.next() {
-                builder.token(TEXT.into(), p);
-                if it.peek().is_some() {
-                    builder.token(WHITESPACE.into(), " ");
-                }
-            }
+            build_text(
+                &mut builder,
+                timestamp.format(CHANGELOG_TIME_FORMAT).to_string().as_str(),
+            );
             builder.finish_node(); // TIMESTAMP
         }
         builder.token(NEWLINE.into(), "\n");
@@ -720,6 +987,7 @@ impl ChangeLog {
             root: self.0.clone(),
             package: None,
             version: None,
+            previous_version: None,
             distributions: None,
             urgency: None,
             maintainer: None,
@@ -734,25 +1002,29 @@ impl ChangeLog {
         })
     }
 
+    /// Start building a new entry at the top of the changelog.
+    ///
+    /// The timestamp defaults to `SOURCE_DATE_EPOCH` if it is set, and the current time otherwise.
     pub fn new_entry(&mut self) -> EntryBuilder {
         let base_entry = self.first_valid_entry();
         let package = base_entry
             .as_ref()
             .and_then(|first_entry| first_entry.package());
-        let mut version = base_entry
+        let previous_version = base_entry
             .as_ref()
             .and_then(|first_entry| first_entry.version());
-        if let Some(version) = version.as_mut() {
-            version.increment_debian();
-        }
+        let version = previous_version
+            .as_ref()
+            .map(|v| crate::version::VersionBump::default().next_version(v));
         EntryBuilder {
             root: self.0.clone(),
             package,
             version,
+            previous_version,
             distributions: Some(vec!["UNRELEASED".into()]),
             urgency: Some(Urgency::default()),
             maintainer: crate::get_maintainer(),
-            timestamp: Some(chrono::Utc::now().into()),
+            timestamp: Some(crate::reproducible::now()),
             change_lines: vec![],
         }
     }
@@ -777,11 +1049,21 @@ impl ChangeLog {
         urgency: Option<Urgency>,
     ) -> Entry {
         match self.first_valid_entry() {
-            Some(entry) if entry.is_unreleased() != Some(false) => {
+            Some(mut entry) if entry.is_unreleased() != Some(false) => {
                 // Add to existing entry
                 entry.add_change_for_author(change, author);
                 // TODO: set timestamp to std::cmp::max(entry.timestamp(), datetime)
-                // TODO: set urgency to std::cmp::max(entry.urgency(), urgency)
+                if let Some(urgency) = urgency {
+                    // Unknown urgencies can not be compared, so leave them alone
+                    let raise = match entry.urgency() {
+                        None => true,
+                        Some(Urgency::Other(_)) => false,
+                        Some(current) => current < urgency,
+                    };
+                    if raise {
+                        entry.set_urgency(urgency);
+                    }
+                }
                 entry
             }
             Some(_entry) => {
@@ -823,6 +1105,88 @@ impl ChangeLog {
         }
     }
 
+    /// Returns the entry with exactly the given version.
+    pub fn entry_by_version(&self, version: &Version) -> Option<Entry> {
+        self.entries()
+            .find(|e| e.version().as_ref() == Some(version))
+    }
+
+    /// Returns the entries whose version falls within a range.
+    ///
+    /// Versions are compared using Debian version ordering. Entries without a
+    /// valid version are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use debian_changelog::ChangeLog;
+    ///
+    /// let changelog: ChangeLog = r#"breezy (3.3.4-1) unstable; urgency=low
+    ///
+    ///   * New upstream release.
+    ///
+    ///  -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+    ///
+    /// breezy (3.3.3-1) unstable; urgency=low
+    ///
+    ///   * New upstream release.
+    ///
+    ///  -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 17 Jun 2023 14:58:57 +0100
+    /// "#.parse().unwrap();
+    /// let from: debversion::Version = "3.3.3-1".parse().unwrap();
+    /// let versions = changelog
+    ///     .entries_in_version_range(from..)
+    ///     .map(|e| e.version().unwrap().to_string())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(versions, vec!["3.3.4-1", "3.3.3-1"]);
+    /// ```
+    pub fn entries_in_version_range<'a, R: std::ops::RangeBounds<Version> + 'a>(
+        &'a self,
+        range: R,
+    ) -> impl Iterator<Item = Entry> + 'a {
+        self.entries()
+            .filter(move |e| e.version().is_some_and(|v| range.contains(&v)))
+    }
+
+    /// Returns the entries with a version newer than the given one.
+    pub fn entries_newer_than(&self, version: &Version) -> impl Iterator<Item = Entry> + '_ {
+        self.entries_in_version_range((
+            std::ops::Bound::Excluded(version.clone()),
+            std::ops::Bound::Unbounded,
+        ))
+    }
+
+    /// Returns the entries with a version between `from` and `to`.
+    ///
+    /// If `inclusive` is true, the entries for `from` and `to` themselves are
+    /// included as well.
+    pub fn entries_between(
+        &self,
+        from: &Version,
+        to: &Version,
+        inclusive: bool,
+    ) -> impl Iterator<Item = Entry> + '_ {
+        let bound = |v: &Version| {
+            if inclusive {
+                std::ops::Bound::Included(v.clone())
+            } else {
+                std::ops::Bound::Excluded(v.clone())
+            }
+        };
+        self.entries_in_version_range((bound(from), bound(to)))
+    }
+
+    /// Returns the index at which an entry for the given version would be inserted.
+    ///
+    /// This is the position of the first entry with a lower version, or the
+    /// number of entries if there is none. Entries without a valid version are
+    /// skipped.
+    pub fn version_insertion_index(&self, version: &Version) -> usize {
+        self.entries()
+            .position(|e| e.version().is_some_and(|v| &v < version))
+            .unwrap_or_else(|| self.entries().count())
+    }
+
     /// Read a changelog file from a path
     pub fn read_path(path: impl AsRef<std::path::Path>) -> Result<ChangeLog, Error> {
         let mut file = std::fs::File::open(path)?;
@@ -836,12 +1200,16 @@ impl ChangeLog {
         Ok(buf.parse()?)
     }
 
-    pub fn read_relaxed<R: std::io::Read>(mut r: R) -> Result<ChangeLog, Error> {
+    /// Read a changelog file from a reader, tolerating syntax errors
+    ///
+    /// Unlike `read`, this always returns a tree; the problems that were found
+    /// while parsing are returned alongside it.
+    pub fn read_relaxed<R: std::io::Read>(mut r: R) -> Result<(ChangeLog, Vec<Diagnostic>), Error> {
         let mut buf = String::new();
         r.read_to_string(&mut buf)?;
 
         let parsed = parse(&buf);
-        Ok(parsed.root().clone_for_update())
+        Ok((parsed.root().clone_for_update(), parsed.errors))
     }
 }
 
@@ -856,7 +1224,7 @@ impl FromStr for ChangeLog {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parsed = parse(s);
-        if parsed.errors.is_empty() {
+        if parsed.errors.iter().all(|e| e.severity != Severity::Error) {
             Ok(parsed.root().clone_for_update())
         } else {
             Err(ParseError(parsed.errors))
@@ -865,13 +1233,18 @@ impl FromStr for ChangeLog {
 }
 
 impl EntryHeader {
-    /// Returns the version of the entry.
+    /// Returns the version of the entry, or None if it is missing or invalid.
     pub fn version(&self) -> Option<Version> {
+        self.try_version().and_then(|v| v.ok())
+    }
+
+    /// Returns the version of the entry, or an error if it is not a valid version.
+    pub fn try_version(&self) -> Option<Result<Version, debversion::ParseError>> {
         self.0.children_with_tokens().find_map(|it| {
             if let Some(token) = it.as_token() {
                 if token.kind() == VERSION {
-                    let text = token.text()[1..token.text().len() - 1].to_string();
-                    return Some(text.parse().unwrap());
+                    let text = &token.text()[1..token.text().len() - 1];
+                    return Some(text.parse());
                 }
             }
             None
@@ -908,20 +1281,138 @@ impl EntryHeader {
         })
     }
 
+    /// Set the distributions of the entry.
+    ///
+    /// Only the distribution names are replaced; the whitespace in front of
+    /// the distributions and anything following them is preserved.
     pub fn set_distributions(&mut self, distributions: Vec<String>) {
-        todo!("set_distributions")
+        let mut elements = vec![];
+        for (i, distribution) in distributions.iter().enumerate() {
+            if i > 0 {
+                elements.push(new_token(WHITESPACE, " "));
+            }
+            elements.push(new_token(IDENTIFIER, distribution));
+        }
+
+        if let Some(node) = self.0.children().find(|it| it.kind() == DISTRIBUTIONS) {
+            let identifiers = node
+                .children_with_tokens()
+                .filter(|it| it.kind() == IDENTIFIER)
+                .map(|it| it.index())
+                .collect::<Vec<_>>();
+            if let (Some(first), Some(last)) = (identifiers.first(), identifiers.last()) {
+                node.splice_children(*first..*last + 1, elements);
+            } else {
+                let index = node
+                    .children_with_tokens()
+                    .take_while(|it| it.kind() == WHITESPACE)
+                    .count();
+                if index == 0 {
+                    elements.insert(0, new_token(WHITESPACE, " "));
+                }
+                node.splice_children(index..index, elements);
+            }
+        } else {
+            let node = new_node(DISTRIBUTIONS, |builder| {
+                builder.token(WHITESPACE.into(), " ");
+                for (i, distribution) in distributions.iter().enumerate() {
+                    if i > 0 {
+                        builder.token(WHITESPACE.into(), " ");
+                    }
+                    builder.token(IDENTIFIER.into(), distribution);
+                }
+            });
+            let index = child_index(&self.0, &[VERSION])
+                .or_else(|| child_index(&self.0, &[IDENTIFIER]))
+                .map(|i| i + 1)
+                .or_else(|| child_index(&self.0, &[METADATA, NEWLINE]))
+                .unwrap_or_else(|| self.0.children_with_tokens().count());
+            self.0.splice_children(index..index, vec![node]);
+        }
     }
 
+    /// Set the version of the entry.
     pub fn set_version(&mut self, version: Version) {
-        todo!("set_version")
+        let token = new_token(VERSION, format!("({})", version.to_string()).as_str());
+        if let Some(index) = child_index(&self.0, &[VERSION]) {
+            self.0.splice_children(index..index + 1, vec![token]);
+        } else if let Some(index) = child_index(&self.0, &[IDENTIFIER]) {
+            self.0.splice_children(
+                index + 1..index + 1,
+                vec![new_token(WHITESPACE, " "), token],
+            );
+        } else {
+            self.0.splice_children(0..0, vec![token]);
+        }
     }
 
+    /// Set the package name of the entry.
     pub fn set_package(&mut self, package: String) {
-        todo!("set_package")
+        let token = new_token(IDENTIFIER, package.as_str());
+        if let Some(index) = child_index(&self.0, &[IDENTIFIER]) {
+            self.0.splice_children(index..index + 1, vec![token]);
+        } else {
+            let mut elements = vec![token];
+            if let Some(first) = self.0.first_child_or_token() {
+                if first.kind() != WHITESPACE && first.kind() != NEWLINE {
+                    elements.push(new_token(WHITESPACE, " "));
+                }
+            }
+            self.0.splice_children(0..0, elements);
+        }
     }
 
+    /// Set a metadata field, e.g. "urgency".
+    ///
+    /// If the key is already present, only its value is replaced. Otherwise a
+    /// new entry is added after the existing ones.
     pub fn set_metadata(&mut self, key: &str, value: &str) {
-        todo!("set_metadata")
+        if let Some(mut entry) = self
+            .metadata_node()
+            .find(|entry| entry.key().as_deref() == Some(key))
+        {
+            entry.set_value(value);
+            return;
+        }
+
+        let entry = new_node(METADATA_ENTRY, |builder| {
+            MetadataEntry::build(builder, key, value)
+        });
+
+        if let Some(node) = self.0.children().find(|it| it.kind() == METADATA) {
+            let last_entry = node
+                .children_with_tokens()
+                .filter(|it| it.kind() == METADATA_ENTRY)
+                .last();
+            let (index, mut elements) = if let Some(last_entry) = last_entry {
+                (
+                    last_entry.index() + 1,
+                    vec![new_token(COMMA, ","), new_token(WHITESPACE, " ")],
+                )
+            } else {
+                let mut elements = vec![];
+                if !node.children_with_tokens().any(|it| it.kind() == SEMICOLON) {
+                    elements.push(new_token(SEMICOLON, ";"));
+                }
+                if node.last_child_or_token().map(|it| it.kind()) != Some(WHITESPACE) {
+                    elements.push(new_token(WHITESPACE, " "));
+                }
+                (node.children_with_tokens().count(), elements)
+            };
+            elements.push(entry);
+            node.splice_children(index..index, elements);
+        } else {
+            let node = new_node(METADATA, |builder| {
+                builder.token(SEMICOLON.into(), ";");
+                builder.token(WHITESPACE.into(), " ");
+                builder.start_node(METADATA_ENTRY.into());
+                MetadataEntry::build(builder, key, value);
+                builder.finish_node();
+            });
+            let index = child_index(&self.0, &[NEWLINE])
+                .unwrap_or_else(|| self.0.children_with_tokens().count());
+            self.0.splice_children(index..index, vec![node]);
+        }
     }
 
     fn metadata_node(&self) -> impl Iterator<Item = MetadataEntry> + '_ {
@@ -943,14 +1434,22 @@ impl EntryHeader {
         })
     }
 
-    /// Returns the urgency of the entry.3
+    /// Returns the urgency of the entry, or None if it is missing or invalid.
     pub fn urgency(&self) -> Option<Urgency> {
-        for (key, value) in self.metadata() {
-            if key.as_str() == "urgency" {
-                return Some(value.parse().unwrap());
-            }
-        }
-        None
+        self.try_urgency().and_then(|u| u.ok())
+    }
+
+    /// Returns the urgency of the entry, or an error if it is not a valid urgency.
+    pub fn try_urgency(&self) -> Option<Result<Urgency, ParseError>> {
+        self.try_urgency_value()
+            .map(|value| value.map(|value| value.urgency()))
+    }
+
+    /// Returns the urgency as written, including any comment.
+    pub fn try_urgency_value(&self) -> Option<Result<UrgencyValue, ParseError>> {
+        self.metadata()
+            .find(|(key, _)| key.as_str() == "urgency")
+            .map(|(_, value)| value.parse())
     }
 }
 
@@ -975,12 +1474,37 @@ impl EntryFooter {
             .filter(|s| !s.is_empty())
     }
 
+    /// Set the name and email address of the maintainer.
     pub fn set_maintainer(&mut self, maintainer: (String, String)) {
-        todo!("set_maintainer")
+        let (name, email) = maintainer;
+        let node = new_node(MAINTAINER, |builder| build_text(builder, name.as_str()));
+        if let Some(index) = child_index(&self.0, &[MAINTAINER]) {
+            self.0.splice_children(index..index + 1, vec![node]);
+        } else {
+            let index = child_index(&self.0, &[INDENT]).map_or(0, |i| i + 1);
+            let next = self.0.children_with_tokens().nth(index).map(|it| it.kind());
+            if next == Some(EMAIL) {
+                self.0
+                    .splice_children(index..index, vec![node, new_token(WHITESPACE, " ")]);
+            } else {
+                self.0.splice_children(index..index, vec![node]);
+            }
+        }
+        self.set_email(email);
     }
 
+    /// Set the email address of the maintainer.
     pub fn set_email(&mut self, email: String) {
-        todo!("set_email")
+        let token = new_token(EMAIL, format!("<{}>", email).as_str());
+        if let Some(index) = child_index(&self.0, &[EMAIL]) {
+            self.0.splice_children(index..index + 1, vec![token]);
+        } else {
+            let index = child_index(&self.0, &[MAINTAINER])
+                .or_else(|| child_index(&self.0, &[INDENT]))
+                .map_or(0, |i| i + 1);
+            self.0
+                .splice_children(index..index, vec![new_token(WHITESPACE, " "), token]);
+        }
     }
 
     pub fn timestamp(&self) -> Option<String> {
@@ -990,13 +1514,33 @@ impl EntryFooter {
             .map(|m| m.text())
     }
 
+    /// Set the timestamp, as a raw string.
+    ///
+    /// If the footer does not have a timestamp yet, it is added after the
+    /// email address, separated by two spaces.
     pub fn set_timestamp(&mut self, timestamp: String) {
-        todo!("set_timestamp")
+        let node = new_node(TIMESTAMP, |builder| build_text(builder, timestamp.as_str()));
+        if let Some(index) = child_index(&self.0, &[TIMESTAMP]) {
+            self.0.splice_children(index..index + 1, vec![node]);
+            return;
+        }
+        let index = child_index(&self.0, &[NEWLINE])
+            .unwrap_or_else(|| self.0.children_with_tokens().count());
+        let previous = index
+            .checked_sub(1)
+            .and_then(|i| self.0.children_with_tokens().nth(i));
+        if previous.map(|it| it.kind()) == Some(WHITESPACE) {
+            self.0
+                .splice_children(index - 1..index, vec![new_token(WHITESPACE, "  "), node]);
+        } else {
+            self.0
+                .splice_children(index..index, vec![new_token(WHITESPACE, "  "), node]);
+        }
     }
 }
 
 impl EntryBody {
-    fn text(&self) -> String {
+    pub(crate) fn text(&self) -> String {
         self.0
             .children_with_tokens()
             .filter_map(|it| {
@@ -1057,7 +1601,7 @@ impl std::fmt::Debug for Entry {
 }
 
 impl Entry {
-    fn header(&self) -> Option<EntryHeader> {
+    pub(crate) fn header(&self) -> Option<EntryHeader> {
         self.0.children().find_map(EntryHeader::cast)
     }
 
@@ -1065,6 +1609,40 @@ impl Entry {
         self.0.children().find_map(EntryFooter::cast)
     }
 
+    /// Return the header line as written, without the trailing newline.
+    pub(crate) fn header_line(&self) -> Option<String> {
+        self.header()
+            .map(|h| h.to_string().trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    /// Return the footer line as written, without the trailing newline.
+    pub(crate) fn footer_line(&self) -> Option<String> {
+        self.footer()
+            .map(|f| f.to_string().trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    /// Return the lines of the body as written, including their indentation.
+    ///
+    /// Leading and trailing empty lines are skipped.
+    pub(crate) fn body_lines(&self) -> Vec<String> {
+        let mut lines = self
+            .0
+            .children()
+            .filter(|n| n.kind() == ENTRY_BODY || n.kind() == EMPTY_LINE)
+            .map(|n| {
+                n.text()
+                    .to_string()
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string()
+            })
+            .skip_while(|l| l.trim().is_empty())
+            .collect::<Vec<_>>();
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+        lines
+    }
+
     /// Return the package name of the entry.
     pub fn package(&self) -> Option<String> {
         self.header().and_then(|h| h.package())
@@ -1077,10 +1655,18 @@ impl Entry {
     }
 
     /// Return the version of the entry.
+    ///
+    /// Returns None if the version is missing or invalid; use `try_version`
+    /// to tell the two apart.
     pub fn version(&self) -> Option<Version> {
         self.header().and_then(|h| h.version())
     }
 
+    /// Return the version of the entry, or an error if it is not a valid version.
+    pub fn try_version(&self) -> Option<Result<Version, debversion::ParseError>> {
+        self.header().and_then(|h| h.try_version())
+    }
+
     pub fn set_version(&mut self, version: Version) {
         self.header()
             .unwrap_or_else(|| self.create_header())
@@ -1109,9 +1695,20 @@ impl Entry {
     }
 
     pub fn set_maintainer(&mut self, maintainer: (String, String)) {
-        self.footer()
-            .unwrap_or_else(|| self.create_footer())
-            .set_maintainer(maintainer);
+        match self.footer() {
+            Some(mut footer) => footer.set_maintainer(maintainer),
+            None => {
+                self.create_footer(maintainer);
+            }
+        }
+    }
+
+    /// Set the email address of the maintainer.
+    ///
+    /// If the entry has no footer yet, the name of the maintainer is taken from the
+    /// environment, like `new_empty_entry` does.
+    pub fn set_email(&mut self, email: String) {
+        self.footer_or_create().set_email(email);
     }
 
     /// Returns the timestamp of the entry, as the raw string.
@@ -1119,38 +1716,131 @@ impl Entry {
         self.footer().and_then(|f| f.timestamp())
     }
 
+    /// Set the timestamp of the entry, as a raw string.
+    ///
+    /// If the entry has no footer yet, the maintainer is taken from the environment, like
+    /// `new_empty_entry` does.
     pub fn set_timestamp(&mut self, timestamp: String) {
-        self.footer()
-            .unwrap_or_else(|| self.create_footer())
-            .set_timestamp(timestamp);
+        self.footer_or_create().set_timestamp(timestamp);
     }
 
     pub fn set_datetime(&mut self, datetime: DateTime<FixedOffset>) {
-        self.set_timestamp(format!("{}", datetime.format("%a, %d %b %Y %H:%M:%S %z")));
+        self.set_timestamp(format!("{}", datetime.format(CHANGELOG_TIME_FORMAT)));
     }
 
     /// Returns the datetime of the entry.
+    ///
+    /// Timestamps that deviate from the canonical format are accepted as long as dpkg
+    /// would accept them; use `parse_timestamp` to find out about the deviations.
     pub fn datetime(&self) -> Option<DateTime<FixedOffset>> {
-        self.timestamp().and_then(|ts| parse_time_string(&ts).ok())
+        self.parse_timestamp()
+            .and_then(|ts| ts.ok())
+            .map(|ts| ts.datetime)
+    }
+
+    /// Parses the timestamp of the entry leniently.
+    ///
+    /// Returns None if the entry has no timestamp.
+    pub fn parse_timestamp(
+        &self,
+    ) -> Option<Result<crate::timestamp::Timestamp, crate::timestamp::Error>> {
+        self.timestamp()
+            .map(|ts| crate::timestamp::parse(ts.as_str()))
     }
 
     /// Returns the urgency of the entry.
+    ///
+    /// Returns None if the urgency is missing or invalid; use `try_urgency`
+    /// to tell the two apart.
     pub fn urgency(&self) -> Option<Urgency> {
         self.header().and_then(|h| h.urgency())
     }
 
+    /// Returns the urgency of the entry, or an error if it is not a valid urgency.
+    pub fn try_urgency(&self) -> Option<Result<Urgency, ParseError>> {
+        self.header().and_then(|h| h.try_urgency())
+    }
+
+    /// Create an empty header line at the start of the entry.
+    ///
+    /// The header only contains a newline; use the setters to fill it in.
     fn create_header(&self) -> EntryHeader {
-        todo!("create_header")
+        let header = new_node(ENTRY_HEADER, |builder| {
+            builder.token(NEWLINE.into(), "\n");
+        });
+        let mut elements = vec![header.clone()];
+        if self.0.first_child().map(|n| n.kind()) != Some(EMPTY_LINE) {
+            elements.push(new_node(EMPTY_LINE, |builder| {
+                builder.token(NEWLINE.into(), "\n");
+            }));
+        }
+        self.0.splice_children(0..0, elements);
+        EntryHeader::cast(header.into_node().unwrap()).unwrap()
+    }
+
+    fn footer_or_create(&self) -> EntryFooter {
+        self.footer()
+            .unwrap_or_else(|| self.create_footer(crate::get_maintainer().unwrap_or_default()))
     }
 
-    fn create_footer(&self) -> EntryFooter {
-        todo!("create_footer")
+    /// Create a footer line for a maintainer after the last change line.
+    ///
+    /// Any empty lines following the changes are kept after the footer, so
+    /// that the entry stays separated from the next one.
+    fn create_footer(&self, maintainer: (String, String)) -> EntryFooter {
+        // Make sure the last line is terminated, e.g. for a truncated file
+        let last_token = self
+            .0
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .last();
+        if let Some(last) = last_token {
+            if last.kind() != NEWLINE {
+                let index = last.index() + 1;
+                last.parent()
+                    .unwrap()
+                    .splice_children(index..index, vec![new_token(NEWLINE, "\n")]);
+            }
+        }
+
+        let footer = new_node(ENTRY_FOOTER, |builder| {
+            builder.token(INDENT.into(), " -- ");
+            builder.token(NEWLINE.into(), "\n");
+        });
+        let index = self
+            .0
+            .children()
+            .filter(|n| n.kind() == ENTRY_BODY || n.kind() == ENTRY_HEADER)
+            .last()
+            .map_or(0, |n| n.index() + 1);
+        let elements = vec![
+            new_node(EMPTY_LINE, |builder| {
+                builder.token(NEWLINE.into(), "\n");
+            }),
+            footer.clone(),
+        ];
+        self.0.splice_children(index..index, elements);
+        let mut footer = EntryFooter::cast(footer.into_node().unwrap()).unwrap();
+        footer.set_maintainer(maintainer);
+        footer
     }
 
     pub fn set_urgency(&mut self, urgency: Urgency) {
         self.set_metadata("urgency", urgency.to_string().as_str());
     }
 
+    /// Returns the urgency as written, preserving case and any comment.
+    pub fn urgency_value(&self) -> Option<UrgencyValue> {
+        self.header()
+            .and_then(|h| h.try_urgency_value())
+            .and_then(|u| u.ok())
+    }
+
+    /// Set the urgency, including case and an optional comment.
+    pub fn set_urgency_value(&mut self, urgency: UrgencyValue) {
+        self.set_metadata("urgency", urgency.to_string().as_str());
+    }
+
     pub fn set_metadata(&mut self, key: &str, value: &str) {
         self.header()
             .unwrap_or_else(|| self.create_header())
@@ -1162,6 +1852,18 @@ impl Entry {
     /// If the author is not the same as the current maintainer, a new
     /// section will be created for the author in the entry (e.g. "[ John Doe ]").
     pub fn add_change_for_author(&self, change: &[&str], author: (String, String)) {
+        self.add_change_for_author_with(change, author, &crate::textwrap::Wrapper::new())
+    }
+
+    /// Add a change for the specified author, rewrapping it with `wrapper`
+    ///
+    /// See [`Entry::add_change_for_author`].
+    pub fn add_change_for_author_with(
+        &self,
+        change: &[&str],
+        author: (String, String),
+        wrapper: &crate::textwrap::Wrapper,
+    ) {
         let changes_lines = self.change_lines().collect::<Vec<_>>();
         let by_author = crate::changes::changes_by_author(changes_lines.iter().map(|s| s.as_str()))
             .collect::<Vec<_>>();
@@ -1196,7 +1898,7 @@ impl Entry {
             }
         }
 
-        for line in crate::textwrap::rewrap_changes(change.iter().copied()) {
+        for line in wrapper.rewrap_changes_lenient(change.iter().copied()) {
             self.append_change_line(line.as_ref());
         }
     }
@@ -1297,8 +1999,16 @@ impl Entry {
         lines.into_iter().skip_while(|it| it.is_empty())
     }
 
-    /// Return whether the entry is marked as being unreleased
-    pub fn is_unreleased(&self) -> Option<bool> {
+    /// Returns the changes of the entry, with wrapped lines joined into logical changes.
+    ///
+    /// See [`crate::changes::unwrap_changes`].
+    pub fn logical_changes(&self) -> Vec<crate::changes::LogicalLine> {
+        let lines = self.change_lines().collect::<Vec<_>>();
+        crate::changes::unwrap_changes(lines.iter().map(|s| s.as_str()))
+    }
+
+    /// Return whether the entry is marked as being unreleased
+    pub fn is_unreleased(&self) -> Option<bool> {
         let distro_is_unreleased = self.distributions().as_ref().map(|ds| {
             let ds = ds.iter().map(|d| d.as_str()).collect::<Vec<&str>>();
             crate::distributions_is_unreleased(ds.as_slice())
@@ -1320,11 +2030,7 @@ impl Entry {
     }
 }
 
-const CHANGELOG_TIME_FORMAT: &str = "%a, %d %b %Y %H:%M:%S %z";
-
-fn parse_time_string(time_str: &str) -> Result<DateTime<FixedOffset>, chrono::ParseError> {
-    DateTime::parse_from_str(time_str, CHANGELOG_TIME_FORMAT)
-}
+pub(crate) const CHANGELOG_TIME_FORMAT: &str = "%a, %d %b %Y %H:%M:%S %z";
 
 #[test]
 fn test_parse_simple() {
@@ -1344,7 +2050,7 @@ breezy (3.3.3-2) unstable; urgency=medium
 # Oh, and here is a comment
 "#;
     let parsed = parse(CHANGELOG);
-    assert_eq!(parsed.errors, Vec::<String>::new());
+    assert_eq!(parsed.errors, Vec::<Diagnostic>::new());
     let node = parsed.syntax();
     assert_eq!(
         format!("{:#?}", node),
@@ -1536,6 +2242,39 @@ fn test_new_entry() {
     assert!(!cl.entries().next().unwrap().is_unreleased().unwrap());
 }
 
+#[test]
+fn test_new_entry_bump() {
+    let mut cl: ChangeLog = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * A change.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+    .parse()
+    .unwrap();
+    let entry = cl
+        .new_entry()
+        .bump(crate::version::VersionBump::Backport(12))
+        .maintainer(("Jelmer Vernooĳ".into(), "jelmer@debian.org".into()))
+        .change_line("* Rebuild for bookworm-backports.".into())
+        .finish();
+    assert_eq!(entry.version(), Some("3.3.4-1~bpo12+1".parse().unwrap()));
+
+    let entry = cl
+        .new_entry()
+        .maintainer(("Jelmer Vernooĳ".into(), "jelmer@debian.org".into()))
+        .change_line("* A change.".into())
+        .finish();
+    assert_eq!(entry.version(), Some("3.3.4-1~bpo12+2".parse().unwrap()));
+
+    let entry = cl
+        .new_empty_entry()
+        .bump(crate::version::VersionBump::BinNmu)
+        .change_line("* A change.".into())
+        .finish();
+    assert_eq!(entry.version(), None);
+}
+
 #[test]
 fn test_new_empty_default() {
     let mut cl = ChangeLog::new();
@@ -1585,7 +2324,20 @@ lintian-brush (0.35) UNRELEASED; urgency=medium
 
  -- Joe Example <joe@example.com>  Fri, 04 Oct 2019 02:36:13 +0000
 "#;
-    let cl = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+    let (cl, diagnostics) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+    assert_eq!(
+        diagnostics[0],
+        Diagnostic {
+            kind: DiagnosticKind::Expected {
+                expected: VERSION,
+                found: Some(IDENTIFIER)
+            },
+            severity: Severity::Error,
+            range: text_range(5, 2),
+            line: 1,
+            column: 6,
+        }
+    );
     let entry = cl.entries().nth(1).unwrap();
     assert_eq!(entry.package(), Some("lintian-brush".into()));
     assert_eq!(entry.version(), Some("0.35".parse().unwrap()));
@@ -1654,6 +2406,37 @@ mod entry_manipulate_tests {
         assert_eq!(entry.package(), None);
         assert_eq!(entry.version(), None);
     }
+
+    #[test]
+    fn test_add_change_for_author_with() {
+        let cl: ChangeLog = r#"blah (1.0-1) UNRELEASED; urgency=medium
+
+  * A change.
+
+ -- Jane Example <jane@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        let entry = cl.entries().next().unwrap();
+
+        entry.add_change_for_author_with(
+            &["* Another change, with a description that does not fit."],
+            ("Jane Example".to_string(), "jane@example.com".to_string()),
+            &crate::textwrap::Wrapper::new().width(40),
+        );
+
+        assert_eq!(
+            r#"blah (1.0-1) UNRELEASED; urgency=medium
+
+  * A change.
+  * Another change, with a description
+    that does not fit.
+
+ -- Jane Example <jane@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#,
+            cl.to_string()
+        );
+    }
 }
 
 #[cfg(test)]
@@ -1699,4 +2482,647 @@ mod auto_add_change_tests {
             ]
         );
     }
+
+    #[test]
+    fn test_logical_changes() {
+        use crate::changes::{Bullet, LogicalChange, LogicalLine};
+        use crate::ChangeLog;
+        let cl: ChangeLog = r#"foo (1.0-1) unstable; urgency=low
+
+  * Initial release. This change is long enough that it
+    was wrapped. Closes: #123456
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        let entry = cl.entries().next().unwrap();
+        assert_eq!(
+            entry.logical_changes(),
+            vec![LogicalLine::Change(LogicalChange {
+                bullet: Bullet::Asterisk,
+                depth: 0,
+                indent: 0,
+                text: "Initial release. This change is long enough that it was wrapped. Closes: #123456".to_string(),
+            })]
+        );
+    }
+}
+
+#[cfg(test)]
+mod entry_header_tests {
+    use super::*;
+
+    const CHANGELOG: &str = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+
+    #[test]
+    fn test_set_version() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_version("3.3.5-1".parse().unwrap());
+        assert_eq!(entry.version(), Some("3.3.5-1".parse().unwrap()));
+        assert_eq!(cl.to_string(), CHANGELOG.replace("(3.3.4-1)", "(3.3.5-1)"));
+    }
+
+    #[test]
+    fn test_set_package() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_package("brz".into());
+        assert_eq!(entry.package(), Some("brz".into()));
+        assert_eq!(cl.to_string(), CHANGELOG.replace("breezy ", "brz "));
+    }
+
+    #[test]
+    fn test_set_distributions() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_distributions(vec!["unstable".into(), "experimental".into()]);
+        assert_eq!(
+            entry.distributions(),
+            Some(vec!["unstable".into(), "experimental".into()])
+        );
+        assert_eq!(
+            cl.to_string(),
+            CHANGELOG.replace("unstable;", "unstable experimental;")
+        );
+        entry.set_distributions(vec!["UNRELEASED".into()]);
+        assert_eq!(
+            cl.to_string(),
+            CHANGELOG.replace("unstable;", "UNRELEASED;")
+        );
+    }
+
+    #[test]
+    fn test_set_urgency() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_urgency(Urgency::High);
+        assert_eq!(entry.urgency(), Some(Urgency::High));
+        assert_eq!(
+            cl.to_string(),
+            CHANGELOG.replace("urgency=low", "urgency=high")
+        );
+    }
+
+    #[test]
+    fn test_set_metadata_new_key() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_metadata("binary-only", "yes");
+        assert_eq!(
+            cl.to_string(),
+            CHANGELOG.replace("urgency=low", "urgency=low, binary-only=yes")
+        );
+        let entry = cl.entries().next().unwrap();
+        assert_eq!(
+            entry.header().unwrap().metadata().collect::<Vec<_>>(),
+            vec![
+                ("urgency".to_string(), "low".to_string()),
+                ("binary-only".to_string(), "yes".to_string())
+            ]
+        );
+
+        // The result should parse again, without errors
+        let reparsed: ChangeLog = cl.to_string().parse().unwrap();
+        assert_eq!(reparsed.to_string(), cl.to_string());
+    }
+
+    #[test]
+    fn test_set_metadata_without_semicolon() {
+        let text = CHANGELOG.replace("; urgency=low", "");
+        let cl: ChangeLog = text.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        assert_eq!(entry.urgency(), None);
+        entry.set_urgency(Urgency::Medium);
+        assert_eq!(entry.urgency(), Some(Urgency::Medium));
+        assert_eq!(
+            cl.to_string(),
+            CHANGELOG.replace("urgency=low", "urgency=medium")
+        );
+    }
+
+    #[test]
+    fn test_preserves_whitespace() {
+        let text = CHANGELOG.replace(
+            "(3.3.4-1) unstable; urgency=low",
+            "(3.3.4-1)  unstable  stable;  urgency=low,  foo=bar",
+        );
+        let cl: ChangeLog = text.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_urgency(Urgency::High);
+        entry.set_version("3.3.4-2".parse().unwrap());
+        assert_eq!(
+            cl.to_string(),
+            CHANGELOG.replace(
+                "(3.3.4-1) unstable; urgency=low",
+                "(3.3.4-2)  unstable  stable;  urgency=high,  foo=bar",
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod entry_footer_tests {
+    use super::*;
+
+    const CHANGELOG: &str = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+
+    #[test]
+    fn test_set_maintainer() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_maintainer(("Joe Example".into(), "joe@example.com".into()));
+        assert_eq!(entry.maintainer(), Some("Joe Example".into()));
+        assert_eq!(entry.email(), Some("joe@example.com".into()));
+        assert_eq!(
+            cl.to_string(),
+            CHANGELOG.replace(
+                "Jelmer Vernooĳ <jelmer@debian.org>",
+                "Joe Example <joe@example.com>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_set_maintainer_before_email() {
+        let node = new_node(ENTRY_FOOTER, |builder| {
+            builder.token(INDENT.into(), " -- ");
+            builder.token(EMAIL.into(), "<joe@example.com>");
+            builder.token(NEWLINE.into(), "\n");
+        });
+        let mut footer = EntryFooter::cast(node.into_node().unwrap()).unwrap();
+        footer.set_maintainer(("Joe Example".into(), "joe@example.com".into()));
+        assert_eq!(footer.to_string(), " -- Joe Example <joe@example.com>\n");
+    }
+
+    #[test]
+    fn test_set_email() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_email("jelmer@jelmer.uk".into());
+        assert_eq!(entry.maintainer(), Some("Jelmer Vernooĳ".into()));
+        assert_eq!(
+            cl.to_string(),
+            CHANGELOG.replace("<jelmer@debian.org>", "<jelmer@jelmer.uk>")
+        );
+    }
+
+    #[test]
+    fn test_datetime_lenient() {
+        let cl: ChangeLog = CHANGELOG
+            .replace("Mon, 04 Sep 2023 18:13:45 -0500", "4 Sep 2023 23:13:45 GMT")
+            .parse()
+            .unwrap();
+        let entry = cl.entries().next().unwrap();
+        assert_eq!(
+            entry.datetime(),
+            Some("2023-09-04T23:13:45+00:00".parse().unwrap())
+        );
+        assert_eq!(
+            entry.parse_timestamp().unwrap().unwrap().deviations,
+            vec![
+                crate::timestamp::Deviation::MissingWeekday,
+                crate::timestamp::Deviation::SingleDigitDay,
+                crate::timestamp::Deviation::NamedTimezone("GMT".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_datetime() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_datetime("2023-09-05T10:00:00+02:00".parse().unwrap());
+        assert_eq!(
+            entry.datetime(),
+            Some("2023-09-05T10:00:00+02:00".parse().unwrap())
+        );
+        assert_eq!(
+            cl.to_string(),
+            CHANGELOG.replace(
+                "Mon, 04 Sep 2023 18:13:45 -0500",
+                "Tue, 05 Sep 2023 10:00:00 +0200"
+            )
+        );
+    }
+
+    #[test]
+    fn test_add_missing_timestamp() {
+        let text = CHANGELOG.replace("  Mon, 04 Sep 2023 18:13:45 -0500", "");
+        let (cl, _) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        assert_eq!(entry.timestamp(), None);
+        entry.set_timestamp("Mon, 04 Sep 2023 18:13:45 -0500".into());
+        assert_eq!(cl.to_string(), CHANGELOG);
+    }
+
+    #[test]
+    fn test_add_missing_maintainer() {
+        let text = CHANGELOG.replace(
+            "Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500",
+            "",
+        );
+        let cl: ChangeLog = text.parse().unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        assert_eq!(entry.maintainer(), None);
+        entry.set_maintainer(("Jelmer Vernooĳ".into(), "jelmer@debian.org".into()));
+        entry.set_datetime("2023-09-04T18:13:45-05:00".parse().unwrap());
+        assert_eq!(cl.to_string(), CHANGELOG);
+    }
+}
+
+#[cfg(test)]
+mod partial_entry_tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_footer() {
+        let text = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+"#;
+        let (cl, _) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        assert_eq!(entry.maintainer(), None);
+        entry.set_maintainer(("Jelmer Vernooĳ".into(), "jelmer@debian.org".into()));
+        entry.set_datetime("2023-09-04T18:13:45-05:00".parse().unwrap());
+        assert_eq!(
+            cl.to_string(),
+            r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        );
+        let reparsed: ChangeLog = cl.to_string().parse().unwrap();
+        assert_eq!(reparsed.entries().count(), 1);
+    }
+
+    #[test]
+    fn test_missing_footer_before_next_entry() {
+        let text = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+breezy (3.3.3-1) unstable; urgency=low
+
+  * Previous release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+        let (cl, _) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_maintainer(("Jelmer Vernooĳ".into(), "jelmer@debian.org".into()));
+        entry.set_timestamp("Tue, 05 Sep 2023 18:13:45 -0500".into());
+        assert_eq!(
+            cl.to_string(),
+            r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Tue, 05 Sep 2023 18:13:45 -0500
+
+breezy (3.3.3-1) unstable; urgency=low
+
+  * Previous release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        );
+        assert!(ChangeLog::read(cl.to_string().as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_missing_footer_set_timestamp() {
+        let text = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+"#;
+        let (cl, _) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_timestamp("Tue, 05 Sep 2023 18:13:45 -0500".into());
+        // The maintainer of a new footer comes from the environment
+        let (name, email) = crate::get_maintainer().unwrap();
+        assert_eq!(entry.maintainer(), Some(name.clone()));
+        assert_eq!(
+            cl.to_string(),
+            format!(
+                "{}\n -- {} <{}>  Tue, 05 Sep 2023 18:13:45 -0500\n",
+                text, name, email
+            )
+        );
+    }
+
+    #[test]
+    fn test_missing_final_newline() {
+        let text = "breezy (3.3.4-1) unstable; urgency=low\n\n  * New upstream release.";
+        let (cl, _) = ChangeLog::read_relaxed(text.as_bytes()).unwrap();
+        let mut entry = cl.entries().next().unwrap();
+        entry.set_maintainer(("Jelmer Vernooĳ".into(), "jelmer@debian.org".into()));
+        assert_eq!(
+            cl.to_string(),
+            r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>
+"#
+        );
+    }
+
+    #[test]
+    fn test_missing_header() {
+        let mut cl = ChangeLog::new();
+        let mut entry = cl
+            .new_empty_entry()
+            .change_line("* A change.".into())
+            .finish();
+        entry.header().unwrap().0.detach();
+        assert!(entry.header().is_none());
+        entry.set_package("breezy".into());
+        entry.set_version("3.3.4-1".parse().unwrap());
+        entry.set_distributions(vec!["unstable".into()]);
+        entry.set_urgency(Urgency::Low);
+        assert_eq!(
+            cl.to_string(),
+            r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * A change.
+
+ -- 
+"#
+        );
+    }
+
+    #[test]
+    fn test_new_empty_entry_fill_in() {
+        let mut cl = ChangeLog::new();
+        let mut entry = cl
+            .new_empty_entry()
+            .change_line("* A change.".into())
+            .finish();
+        entry.set_package("breezy".into());
+        entry.set_version("3.3.4-1".parse().unwrap());
+        entry.set_distributions(vec!["unstable".into()]);
+        entry.set_urgency(Urgency::Low);
+        entry.set_maintainer(("Jelmer Vernooĳ".into(), "jelmer@debian.org".into()));
+        entry.set_datetime("2023-09-04T18:13:45-05:00".parse().unwrap());
+        assert_eq!(
+            cl.to_string(),
+            r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * A change.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        );
+    }
+}
+
+#[cfg(test)]
+mod invalid_value_tests {
+    use super::*;
+
+    const CHANGELOG: &str = r#"breezy (3.3.4_1) unstable; urgency=42
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+
+    #[test]
+    fn test_accessors() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let entry = cl.entries().next().unwrap();
+        assert_eq!(entry.version(), None);
+        assert!(matches!(entry.try_version(), Some(Err(_))));
+        assert_eq!(entry.urgency(), None);
+        assert!(matches!(entry.try_urgency(), Some(Err(_))));
+        // Should not panic
+        let _ = format!("{:?}", entry);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let (_, diagnostics) = ChangeLog::read_relaxed(CHANGELOG.as_bytes()).unwrap();
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic {
+                    kind: DiagnosticKind::InvalidVersion("3.3.4_1".to_string()),
+                    severity: Severity::Warning,
+                    range: text_range(8, 7),
+                    line: 1,
+                    column: 9,
+                },
+                Diagnostic {
+                    kind: DiagnosticKind::InvalidUrgency("42".to_string()),
+                    severity: Severity::Warning,
+                    range: text_range(35, 2),
+                    line: 1,
+                    column: 36,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing() {
+        let (cl, _) = ChangeLog::read_relaxed("breezy\n".as_bytes()).unwrap();
+        let entry = cl.entries().next().unwrap();
+        assert!(entry.try_version().is_none());
+        assert!(entry.try_urgency().is_none());
+    }
+}
+
+#[cfg(test)]
+mod urgency_tests {
+    use super::*;
+
+    #[test]
+    fn test_ordering() {
+        assert!(Urgency::Low < Urgency::Medium);
+        assert!(Urgency::High < Urgency::Critical);
+        assert!(Urgency::Critical < Urgency::Emergency);
+        assert!(Urgency::Other("unknown".to_string()) < Urgency::Low);
+        assert_eq!(
+            std::cmp::max(Urgency::Medium, Urgency::Other("foo".to_string())),
+            Urgency::Medium
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("MEDIUM".parse::<Urgency>().unwrap(), Urgency::Medium);
+        assert_eq!(
+            "Extreme".parse::<Urgency>().unwrap(),
+            Urgency::Other("extreme".to_string())
+        );
+        assert!("medium (was low)".parse::<Urgency>().is_err());
+        assert!("".parse::<Urgency>().is_err());
+    }
+
+    #[test]
+    fn test_value() {
+        let value: UrgencyValue = "MEDIUM (was low)".parse().unwrap();
+        assert_eq!(value.urgency(), Urgency::Medium);
+        assert_eq!(value.value(), "MEDIUM");
+        assert_eq!(value.comment(), Some("was low"));
+        assert_eq!(value.to_string(), "MEDIUM (was low)");
+        assert_eq!(
+            UrgencyValue::new(Urgency::High, Some("fixes RC bug")).to_string(),
+            "high (fixes RC bug)"
+        );
+    }
+
+    #[test]
+    fn test_entry_with_comment() {
+        let text = r#"breezy (3.3.4-1) unstable; urgency=MEDIUM (was low)
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+        let cl: ChangeLog = text.parse().unwrap();
+        assert_eq!(cl.to_string(), text);
+        let mut entry = cl.entries().next().unwrap();
+        assert_eq!(entry.urgency(), Some(Urgency::Medium));
+        let value = entry.urgency_value().unwrap();
+        assert_eq!(value.comment(), Some("was low"));
+
+        entry.set_urgency_value(UrgencyValue::new(Urgency::High, Some("was medium")));
+        assert_eq!(
+            cl.to_string(),
+            text.replace("MEDIUM (was low)", "high (was medium)")
+        );
+        entry.set_urgency_value(value);
+        assert_eq!(cl.to_string(), text);
+
+        let reparsed: ChangeLog = cl.to_string().parse().unwrap();
+        assert_eq!(
+            reparsed.entries().next().unwrap().urgency(),
+            Some(Urgency::Medium)
+        );
+    }
+
+    #[test]
+    fn test_auto_add_change_raises_urgency() {
+        let text = r#"lintian-brush (0.35) UNRELEASED; urgency=medium
+
+  * This line already existed.
+
+ -- Joe Example <joe@example.com>  Fri, 04 Oct 2019 02:36:13 +0000
+"#;
+        let mut cl: ChangeLog = text.parse().unwrap();
+        let author = ("Joe Example".to_string(), "joe@example.com".to_string());
+        let entry = cl.auto_add_change(&["* A change."], author.clone(), None, Some(Urgency::Low));
+        assert_eq!(entry.urgency(), Some(Urgency::Medium));
+        let entry = cl.auto_add_change(&["* A change."], author, None, Some(Urgency::High));
+        assert_eq!(entry.urgency(), Some(Urgency::High));
+    }
+
+    #[test]
+    fn test_auto_add_change_keeps_unknown_urgency() {
+        let text = r#"lintian-brush (0.35) UNRELEASED; urgency=extreme
+
+  * This line already existed.
+
+ -- Joe Example <joe@example.com>  Fri, 04 Oct 2019 02:36:13 +0000
+"#;
+        let mut cl: ChangeLog = text.parse().unwrap();
+        let author = ("Joe Example".to_string(), "joe@example.com".to_string());
+        let entry = cl.auto_add_change(&["* A change."], author, None, Some(Urgency::Low));
+        assert_eq!(entry.urgency(), Some(Urgency::Other("extreme".to_string())));
+    }
+}
+
+#[cfg(test)]
+mod version_range_tests {
+    use super::*;
+
+    const CHANGELOG: &str = r#"foo (1.4-3) unstable; urgency=low
+
+  * Change.
+
+ -- Jane Doe <jane@example.com>  Thu, 07 Sep 2023 18:13:45 -0500
+
+foo (1.4-1) unstable; urgency=low
+
+  * Change.
+
+ -- Jane Doe <jane@example.com>  Wed, 06 Sep 2023 18:13:45 -0500
+
+foo (1.3-1) unstable; urgency=low
+
+  * Change.
+
+ -- Jane Doe <jane@example.com>  Tue, 05 Sep 2023 18:13:45 -0500
+
+foo (1.2-1) unstable; urgency=low
+
+  * Change.
+
+ -- Jane Doe <jane@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+
+    fn versions(entries: impl Iterator<Item = Entry>) -> Vec<String> {
+        entries.map(|e| e.version().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn test_entry_by_version() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let entry = cl.entry_by_version(&"1.3-1".parse().unwrap()).unwrap();
+        assert_eq!(entry.version(), Some("1.3-1".parse().unwrap()));
+        assert!(cl.entry_by_version(&"1.3-2".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_entries_newer_than() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        assert_eq!(
+            versions(cl.entries_newer_than(&"1.3-1".parse().unwrap())),
+            vec!["1.4-3", "1.4-1"]
+        );
+        assert_eq!(
+            versions(cl.entries_newer_than(&"1.3.1-1".parse().unwrap())),
+            vec!["1.4-3", "1.4-1"]
+        );
+    }
+
+    #[test]
+    fn test_entries_between() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let from = "1.2-1".parse().unwrap();
+        let to = "1.4-3".parse().unwrap();
+        assert_eq!(
+            versions(cl.entries_between(&from, &to, true)),
+            vec!["1.4-3", "1.4-1", "1.3-1", "1.2-1"]
+        );
+        assert_eq!(
+            versions(cl.entries_between(&from, &to, false)),
+            vec!["1.4-1", "1.3-1"]
+        );
+    }
+
+    #[test]
+    fn test_version_insertion_index() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        assert_eq!(cl.version_insertion_index(&"1.5-1".parse().unwrap()), 0);
+        assert_eq!(cl.version_insertion_index(&"1.4-2".parse().unwrap()), 1);
+        assert_eq!(cl.version_insertion_index(&"1.4-1".parse().unwrap()), 2);
+        assert_eq!(cl.version_insertion_index(&"1.0-1".parse().unwrap()), 4);
+    }
 }
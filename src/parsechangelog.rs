@@ -0,0 +1,547 @@
+//! Field extraction compatible with dpkg-parsechangelog(1).
+//!
+//! This produces the same set of fields as `dpkg-parsechangelog`, so that
+//! callers do not have to shell out to it.
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::ChangeLog;
+//! use debian_changelog::parsechangelog::Range;
+//!
+//! let changelog: ChangeLog = r#"breezy (3.3.4-1) unstable; urgency=low
+//!
+//!   * New upstream release. Closes: #1039011
+//!
+//!  -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+//! "#.parse().unwrap();
+//! let fields = changelog.dpkg_fields(&Range::default()).unwrap();
+//! assert_eq!(fields.to_string(), r#"Source: breezy
+//! Version: 3.3.4-1
+//! Distribution: unstable
+//! Urgency: low
+//! Maintainer: Jelmer Vernooĳ <jelmer@debian.org>
+//! Timestamp: 1693869225
+//! Date: Mon, 04 Sep 2023 18:13:45 -0500
+//! Closes: 1039011
+//! Changes:
+//!  breezy (3.3.4-1) unstable; urgency=low
+//!  .
+//!    * New upstream release. Closes: #1039011
+//! "#);
+//! ```
+
+use crate::{ChangeLog, Entry, Urgency};
+use debversion::Version;
+
+/// Selection of entries, with the same semantics as the options of
+/// dpkg-parsechangelog.
+///
+/// The default selects only the most recent entry, like dpkg-parsechangelog
+/// does when no options are given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Range {
+    /// Include all changes later than this version (`--since`).
+    pub since: Option<Version>,
+    /// Include all changes earlier than this version (`--until`).
+    pub until: Option<Version>,
+    /// Include all changes equal to or later than this version (`--from`).
+    pub from: Option<Version>,
+    /// Include all changes up to or equal to this version (`--to`).
+    pub to: Option<Version>,
+    /// Include this many entries from the top, or from the tail if negative (`--count`).
+    pub count: Option<isize>,
+    /// Start counting at this entry, from the top or from the tail if negative (`--offset`).
+    pub offset: Option<isize>,
+    /// Include all entries (`--all`).
+    pub all: bool,
+}
+
+impl Range {
+    /// Select all entries.
+    pub fn all() -> Self {
+        Self {
+            all: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.since.is_none()
+            && self.until.is_none()
+            && self.from.is_none()
+            && self.to.is_none()
+            && self.count.is_none()
+            && self.offset.is_none()
+    }
+
+    /// Resolve conflicting options and versions that do not exist, like dpkg does.
+    fn sanitize(&mut self, versions: &[Version]) {
+        if self.offset.is_some() && self.count.is_none() {
+            self.offset = None;
+        }
+        if self.count.is_some() || self.offset.is_some() {
+            self.from = None;
+            self.since = None;
+            self.to = None;
+            self.until = None;
+        }
+        if self.from.is_some() && self.since.is_some() {
+            self.from = None;
+        }
+        if self.to.is_some() && self.until.is_some() {
+            self.to = None;
+        }
+
+        if let Some(since) = self.since.take() {
+            if versions.contains(&since) {
+                self.since = Some(since);
+            } else if let Some(v) = versions.iter().find(|v| **v < since) {
+                // Use the newest entry that is earlier than the one specified
+                self.since = Some(v.clone());
+            } else {
+                // None found, start from the oldest entry
+                self.from = versions.last().cloned();
+            }
+        }
+        if let Some(from) = self.from.take() {
+            // Use the oldest entry that is later than the one specified
+            self.from = if versions.contains(&from) {
+                Some(from)
+            } else {
+                versions.iter().rev().find(|v| **v > from).cloned()
+            };
+        }
+        if let Some(until) = self.until.take() {
+            // Use the oldest entry that is later than the one specified
+            self.until = if versions.contains(&until) {
+                Some(until)
+            } else {
+                versions.iter().rev().find(|v| **v > until).cloned()
+            };
+        }
+        if let Some(to) = self.to.take() {
+            // Use the newest entry that is earlier than the one specified
+            self.to = if versions.contains(&to) {
+                Some(to)
+            } else {
+                versions.iter().find(|v| **v < to).cloned()
+            };
+        }
+
+        if self.since.is_some() && self.since.as_ref() == versions.first() {
+            self.since = None;
+        }
+        if self.until.is_some() && self.until.as_ref() == versions.last() {
+            self.until = None;
+        }
+    }
+}
+
+/// The fields produced by dpkg-parsechangelog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fields {
+    /// The source package of the most recent entry, or "unknown" if it is missing.
+    pub source: String,
+    /// The version of the most recent entry, or "unknown" if it is missing.
+    pub version: String,
+    /// The distributions of the most recent entry, separated by spaces, or an empty string
+    /// if they are missing.
+    pub distribution: String,
+    /// The highest urgency of the entries, or "unknown" if none of them has one.
+    pub urgency: String,
+    /// The maintainer of the most recent entry, as "Name <email>", or an empty string if it
+    /// is missing.
+    pub maintainer: String,
+    /// The timestamp of the most recent entry, in seconds since the epoch.
+    pub timestamp: Option<i64>,
+    /// The date of the most recent entry, as written.
+    pub date: String,
+    /// Bugs closed by any of the entries, in ascending order.
+    pub closes: Vec<u32>,
+    /// The header and body of each entry, starting with an empty line.
+    pub changes: String,
+    /// Any other metadata fields from the headers, e.g. "Binary-Only".
+    pub extra: Vec<(String, String)>,
+}
+
+/// Capitalize a field name the way dpkg does, e.g. "binary-only" => "Binary-Only".
+fn capitalize_field(name: &str) -> String {
+    name.split('-')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(c) => c
+                    .to_uppercase()
+                    .chain(chars.flat_map(|c| c.to_lowercase()))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+/// Return the numbers of the bugs closed by an entry.
+fn bug_numbers(entry: &Entry) -> Vec<u32> {
+    entry.closes().into_iter().map(|r| r.bug).collect()
+}
+
+/// Return the changes of an entry in the format used by dpkg.
+fn dpkg_changes(entry: &Entry) -> String {
+    let mut ret = format!("\n{}\n\n", entry.header_line().unwrap_or_default());
+    ret.push_str(entry.body_lines().join("\n").as_str());
+    ret
+}
+
+impl Fields {
+    /// Returns the fields in the order dpkg-parsechangelog outputs them.
+    pub fn iter(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        let mut ret = vec![("Source".to_string(), self.source.clone())];
+        let (binary_only, extra): (Vec<_>, Vec<_>) = self
+            .extra
+            .iter()
+            .cloned()
+            .partition(|(name, _)| name == "Binary-Only");
+        ret.extend(binary_only);
+        ret.push(("Version".to_string(), self.version.clone()));
+        ret.push(("Distribution".to_string(), self.distribution.clone()));
+        ret.push(("Urgency".to_string(), self.urgency.clone()));
+        ret.push(("Maintainer".to_string(), self.maintainer.clone()));
+        if let Some(timestamp) = self.timestamp {
+            ret.push(("Timestamp".to_string(), timestamp.to_string()));
+        }
+        ret.push(("Date".to_string(), self.date.clone()));
+        if !self.closes.is_empty() {
+            let closes = self
+                .closes
+                .iter()
+                .map(|b| b.to_string())
+                .collect::<Vec<_>>();
+            ret.push(("Closes".to_string(), closes.join(" ")));
+        }
+        ret.push(("Changes".to_string(), self.changes.clone()));
+        ret.extend(extra);
+        ret.into_iter()
+    }
+
+    /// Look up a field by name, case-insensitively.
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+}
+
+impl std::fmt::Display for Fields {
+    /// Format the fields as a deb822 paragraph.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (name, value) in self.iter() {
+            let mut lines = value.split('\n');
+            let first = lines.next().unwrap_or("");
+            if first.is_empty() {
+                writeln!(f, "{}:", name)?;
+            } else {
+                writeln!(f, "{}: {}", name, first)?;
+            }
+            for line in lines {
+                if line.trim().is_empty() {
+                    writeln!(f, " .")?;
+                } else {
+                    writeln!(f, " {}", line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ChangeLog {
+    /// Returns the entries selected by a range, like dpkg-parsechangelog does.
+    pub fn entries_in_range(&self, range: &Range) -> Vec<Entry> {
+        let entries = self.entries().collect::<Vec<_>>();
+        if range.all {
+            return entries;
+        }
+        let mut range = range.clone();
+        if range.is_empty() {
+            range.count = Some(1);
+        }
+        let versions = entries
+            .iter()
+            .filter_map(|e| e.version())
+            .collect::<Vec<_>>();
+        range.sanitize(versions.as_slice());
+
+        if let Some(count) = range.count {
+            let last = entries.len() as isize - 1;
+            let mut offset = range.offset.unwrap_or(0);
+            if offset > 0 {
+                offset -= (count < 0) as isize;
+            } else if offset < 0 {
+                offset += last + (count > 0) as isize;
+            } else if count < 0 {
+                offset = last;
+            }
+            let mut start = offset;
+            let mut end = offset;
+            if count < 0 {
+                start += count + 1;
+            }
+            if count > 0 {
+                end += count - 1;
+            }
+            start = start.max(0);
+            if start > last {
+                return vec![];
+            }
+            end = end.min(last);
+            if end < 0 {
+                return vec![];
+            }
+            end = end.max(start);
+            return entries
+                .into_iter()
+                .skip(start as usize)
+                .take((end - start + 1) as usize)
+                .collect();
+        }
+
+        let mut ret = vec![];
+        let mut include = range.to.is_none() && range.until.is_none();
+        for entry in entries {
+            let version = entry.version();
+            if range.to.is_some() && version == range.to {
+                include = true;
+            }
+            if range.since.is_some() && version == range.since {
+                break;
+            }
+            if include {
+                ret.push(entry);
+            }
+            if range.until.is_some() && version == range.until {
+                include = true;
+            }
+            if range.from.is_some() && version == range.from {
+                break;
+            }
+        }
+        ret
+    }
+
+    /// Returns the fields that dpkg-parsechangelog would produce for a range of entries.
+    ///
+    /// Returns None if the range does not select any entries.
+    pub fn dpkg_fields(&self, range: &Range) -> Option<Fields> {
+        let entries = self.entries_in_range(range);
+        let first = entries.first()?;
+
+        let mut urgency = first.urgency();
+        let mut closes = bug_numbers(first);
+        let mut extra: Vec<(String, String)> = vec![];
+        let mut changes = dpkg_changes(first);
+
+        for (i, entry) in entries.iter().enumerate() {
+            if i > 0 {
+                if let Some(new) = entry.urgency() {
+                    if urgency.as_ref().map_or(0, Urgency::rank) < new.rank() {
+                        urgency = Some(new);
+                    }
+                }
+                changes.push('\n');
+                changes.push_str(dpkg_changes(entry).as_str());
+                closes.extend(bug_numbers(entry));
+            }
+            for (key, value) in entry
+                .header()
+                .into_iter()
+                .flat_map(|h| h.metadata().collect::<Vec<_>>())
+            {
+                let name = capitalize_field(key.as_str());
+                if name == "Urgency" {
+                    continue;
+                } else if name == "Closes" {
+                    closes.extend(
+                        value
+                            .split_whitespace()
+                            .filter_map(|b| b.parse::<u32>().ok()),
+                    );
+                } else if !extra.iter().any(|(n, _)| *n == name) {
+                    extra.push((name, value));
+                }
+            }
+        }
+        closes.sort();
+        closes.dedup();
+
+        Some(Fields {
+            source: first.package().unwrap_or_else(|| "unknown".to_string()),
+            version: first
+                .version()
+                .map_or_else(|| "unknown".to_string(), |v| v.to_string()),
+            distribution: first.distributions().unwrap_or_default().join(" "),
+            urgency: urgency.map_or_else(|| "unknown".to_string(), |u| u.to_string()),
+            maintainer: match (first.maintainer(), first.email()) {
+                (Some(name), Some(email)) => format!("{} <{}>", name, email),
+                (Some(name), None) => name,
+                (None, Some(email)) => format!("<{}>", email),
+                (None, None) => String::new(),
+            },
+            timestamp: first.datetime().map(|dt| dt.timestamp()),
+            date: first.timestamp().unwrap_or_default(),
+            closes,
+            changes,
+            extra,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+
+breezy (3.3.3-2) unstable; urgency=medium
+
+  * Drop unnecessary dependency on python3-six. Closes: #1039011
+
+  * Drop dependency on cython3-dbg. Closes: #1040544
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 24 Jun 2023 14:58:57 +0100
+
+breezy (3.3.3-1) unstable; urgency=low, binary-only=yes
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 17 Jun 2023 14:58:57 +0100
+"#;
+
+    fn versions(cl: &ChangeLog, range: Range) -> Vec<String> {
+        cl.entries_in_range(&range)
+            .iter()
+            .map(|e| e.version().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_ranges() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let v = |s: &str| Some(s.parse::<Version>().unwrap());
+        assert_eq!(versions(&cl, Range::default()), vec!["3.3.4-1"]);
+        assert_eq!(
+            versions(&cl, Range::all()),
+            vec!["3.3.4-1", "3.3.3-2", "3.3.3-1"]
+        );
+        let since = Range {
+            since: v("3.3.3-1"),
+            ..Default::default()
+        };
+        assert_eq!(versions(&cl, since), vec!["3.3.4-1", "3.3.3-2"]);
+        let from = Range {
+            from: v("3.3.3-2"),
+            ..Default::default()
+        };
+        assert_eq!(versions(&cl, from), vec!["3.3.4-1", "3.3.3-2"]);
+        let until = Range {
+            until: v("3.3.3-2"),
+            ..Default::default()
+        };
+        assert_eq!(versions(&cl, until), vec!["3.3.3-1"]);
+        let to = Range {
+            to: v("3.3.3-2"),
+            ..Default::default()
+        };
+        assert_eq!(versions(&cl, to), vec!["3.3.3-2", "3.3.3-1"]);
+        // Non-existing versions use the nearest entry
+        let since = Range {
+            since: v("3.3.3-1.1"),
+            ..Default::default()
+        };
+        assert_eq!(versions(&cl, since), vec!["3.3.4-1", "3.3.3-2"]);
+    }
+
+    #[test]
+    fn test_count_offset() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let range = |count, offset| Range {
+            count: Some(count),
+            offset,
+            ..Default::default()
+        };
+        assert_eq!(versions(&cl, range(2, None)), vec!["3.3.4-1", "3.3.3-2"]);
+        assert_eq!(versions(&cl, range(1, Some(1))), vec!["3.3.3-2"]);
+        assert_eq!(versions(&cl, range(-1, None)), vec!["3.3.3-1"]);
+        assert_eq!(versions(&cl, range(-2, None)), vec!["3.3.3-2", "3.3.3-1"]);
+        assert_eq!(versions(&cl, range(1, Some(5))), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_multiple_entries() {
+        let cl: ChangeLog = CHANGELOG.parse().unwrap();
+        let fields = cl.dpkg_fields(&Range::all()).unwrap();
+        assert_eq!(fields.urgency, "medium");
+        assert_eq!(fields.closes, vec![1039011, 1040544]);
+        assert_eq!(fields.get("binary-only"), Some("yes".to_string()));
+        assert_eq!(
+            fields.to_string(),
+            r#"Source: breezy
+Binary-Only: yes
+Version: 3.3.4-1
+Distribution: unstable
+Urgency: medium
+Maintainer: Jelmer Vernooĳ <jelmer@debian.org>
+Timestamp: 1693869225
+Date: Mon, 04 Sep 2023 18:13:45 -0500
+Closes: 1039011 1040544
+Changes:
+ breezy (3.3.4-1) unstable; urgency=low
+ .
+   * New upstream release.
+ .
+ breezy (3.3.3-2) unstable; urgency=medium
+ .
+   * Drop unnecessary dependency on python3-six. Closes: #1039011
+ .
+   * Drop dependency on cython3-dbg. Closes: #1040544
+ .
+ breezy (3.3.3-1) unstable; urgency=low, binary-only=yes
+ .
+   * New upstream release.
+"#
+        );
+    }
+
+    #[test]
+    fn test_highest_urgency() {
+        let cl: ChangeLog = r#"breezy (3.3.4-2) unstable; urgency=critical
+
+  * Fix a security issue.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Tue, 05 Sep 2023 18:13:45 -0500
+
+breezy (3.3.4-1) unstable; urgency=emergency
+
+  * Fix data loss.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        let range = Range {
+            count: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(cl.dpkg_fields(&range).unwrap().urgency, "emergency");
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(ChangeLog::new().dpkg_fields(&Range::default()), None);
+    }
+}
@@ -0,0 +1,192 @@
+//! Helpers for reproducible builds, based on `SOURCE_DATE_EPOCH`.
+//!
+//! See <https://reproducible-builds.org/specs/source-date-epoch/>.
+//!
+//! # Example
+//!
+//! ```
+//! let changelog: debian_changelog::ChangeLog = r#"breezy (3.3.4-1) unstable; urgency=low
+//!
+//!   * New upstream release.
+//!
+//!  -- Jelmer Vernooĳ <jelmer@debian.org>  Mon, 04 Sep 2023 18:13:45 -0500
+//! "#.parse().unwrap();
+//! assert_eq!(changelog.source_date_epoch_from_env(|_| None), 1693869225);
+//! ```
+
+use crate::{ChangeLog, Entry};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+/// The name of the environment variable.
+pub const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// Get the time from `SOURCE_DATE_EPOCH`, using the given function to look up variables.
+///
+/// Returns None if the variable is not set or is not a valid number of seconds.
+pub fn source_date_epoch_from_env(
+    get_env: impl Fn(&str) -> Option<String>,
+) -> Option<DateTime<FixedOffset>> {
+    let value = get_env(SOURCE_DATE_EPOCH)?;
+    let seconds = value.trim().parse::<i64>().ok()?;
+    Utc.timestamp_opt(seconds, 0).single().map(|dt| dt.into())
+}
+
+/// Get the time from the `SOURCE_DATE_EPOCH` environment variable, if it is set.
+pub fn source_date_epoch() -> Option<DateTime<FixedOffset>> {
+    source_date_epoch_from_env(|s| std::env::var(s).ok())
+}
+
+/// The time to use for new timestamps, using the given function to look up variables.
+///
+/// This is `SOURCE_DATE_EPOCH` if it is set, and the current time otherwise.
+pub fn now_from_env(get_env: impl Fn(&str) -> Option<String>) -> DateTime<FixedOffset> {
+    source_date_epoch_from_env(get_env).unwrap_or_else(|| Utc::now().into())
+}
+
+/// The time to use for new timestamps: `SOURCE_DATE_EPOCH` if it is set, the current time
+/// otherwise.
+pub fn now() -> DateTime<FixedOffset> {
+    now_from_env(|s| std::env::var(s).ok())
+}
+
+impl Entry {
+    /// Return the value of `SOURCE_DATE_EPOCH` for a build of this entry.
+    ///
+    /// Like dpkg-buildpackage, this keeps the value of `SOURCE_DATE_EPOCH` if it is already
+    /// set. Otherwise, this is the timestamp of the entry, falling back to the current time
+    /// if the timestamp is missing or cannot be parsed.
+    pub fn source_date_epoch(&self) -> i64 {
+        self.source_date_epoch_from_env(|s| std::env::var(s).ok())
+    }
+
+    /// Like [`Entry::source_date_epoch`], using the given function to look up variables.
+    pub fn source_date_epoch_from_env(&self, get_env: impl Fn(&str) -> Option<String>) -> i64 {
+        source_date_epoch_from_env(&get_env)
+            .or_else(|| self.datetime())
+            .unwrap_or_else(|| now_from_env(get_env))
+            .timestamp()
+    }
+}
+
+impl ChangeLog {
+    /// Return the value of `SOURCE_DATE_EPOCH` for a build of the newest entry.
+    ///
+    /// Like dpkg-buildpackage, this keeps the value of `SOURCE_DATE_EPOCH` if it is already
+    /// set. Otherwise, this is the timestamp of the newest entry, falling back to the current
+    /// time if there are no entries, or if the timestamp is missing or cannot be parsed.
+    pub fn source_date_epoch(&self) -> i64 {
+        self.source_date_epoch_from_env(|s| std::env::var(s).ok())
+    }
+
+    /// Like [`ChangeLog::source_date_epoch`], using the given function to look up variables.
+    pub fn source_date_epoch_from_env(&self, get_env: impl Fn(&str) -> Option<String>) -> i64 {
+        match self.entries().next() {
+            Some(entry) => entry.source_date_epoch_from_env(get_env),
+            None => now_from_env(get_env).timestamp(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn fixed_env(name: &str) -> Option<String> {
+        (name == SOURCE_DATE_EPOCH).then(|| "1693869225".to_string())
+    }
+
+    #[test]
+    fn test_from_env() {
+        let env = |value: &'static str| move |_: &str| Some(value.to_string());
+        assert_eq!(
+            source_date_epoch_from_env(env("1693869225")),
+            Some("2023-09-04T23:13:45+00:00".parse().unwrap())
+        );
+        assert_eq!(source_date_epoch_from_env(env("yesterday")), None);
+        assert_eq!(source_date_epoch_from_env(|_| None), None);
+    }
+
+    #[test]
+    fn test_fallback() {
+        let changelog: ChangeLog = r#"breezy (3.3.4-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  yesterday
+
+breezy (3.3.3-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 17 Jun 2023 14:58:57 +0100
+"#
+        .parse()
+        .unwrap();
+        let before = Utc::now().timestamp();
+        assert!(changelog.source_date_epoch_from_env(|_| None) >= before);
+        assert_eq!(
+            changelog
+                .entries()
+                .nth(1)
+                .unwrap()
+                .source_date_epoch_from_env(|_| None),
+            1687010337
+        );
+    }
+
+    #[test]
+    fn test_env_is_kept() {
+        let changelog: ChangeLog = r#"breezy (3.3.3-1) unstable; urgency=low
+
+  * New upstream release.
+
+ -- Jelmer Vernooĳ <jelmer@debian.org>  Sat, 17 Jun 2023 14:58:57 +0100
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(changelog.source_date_epoch_from_env(|_| None), 1687010337);
+        assert_eq!(changelog.source_date_epoch_from_env(fixed_env), 1693869225);
+        let entry = changelog.entries().next().unwrap();
+        assert_eq!(entry.source_date_epoch_from_env(|_| None), 1687010337);
+        assert_eq!(entry.source_date_epoch_from_env(fixed_env), 1693869225);
+    }
+
+    #[test]
+    fn test_now() {
+        assert_eq!(
+            now_from_env(fixed_env),
+            "2023-09-04T23:13:45+00:00"
+                .parse::<DateTime<FixedOffset>>()
+                .unwrap()
+        );
+        let before = Utc::now();
+        assert!(now_from_env(|_| None) >= before);
+    }
+
+    #[test]
+    fn test_new_timestamps() {
+        // New timestamps come from now(), which honours SOURCE_DATE_EPOCH if it is set
+        let before = now().timestamp();
+        let mut changelog = ChangeLog::new();
+        let mut entry = changelog
+            .new_entry()
+            .package("breezy".to_string())
+            .version("3.3.4-1".parse().unwrap())
+            .distributions(vec!["unstable".to_string()])
+            .maintainer(("Jane".to_string(), "jane@example.com".to_string()))
+            .change_line("* New upstream release.".to_string())
+            .finish();
+        let timestamp = entry.datetime().unwrap().timestamp();
+        assert!(timestamp >= before && timestamp <= now().timestamp());
+
+        entry.set_timestamp("Sat, 17 Jun 2023 14:58:57 +0100".to_string());
+        let before = now().timestamp();
+        assert!(crate::release(
+            &mut changelog,
+            Some(vec!["unstable".to_string()]),
+            None,
+            Some(("Jane".to_string(), "jane@example.com".to_string())),
+        ));
+        let timestamp = changelog.entries().next().unwrap().datetime().unwrap();
+        assert!(timestamp.timestamp() >= before && timestamp.timestamp() <= now().timestamp());
+    }
+}
@@ -3,95 +3,172 @@
 //! These functions are used to wrap text for use in a changelog.
 //! The main function is `textwrap`, which takes a string and wraps it to a
 //! specified width, without breaking in between "Closes: #XXXXXX" fragments.
-
-use lazy_regex::{regex_captures};
+//!
+//! The fragments that are never broken are configurable through [`UnbreakableTokens`]:
+//!
+//! ```
+//! use debian_changelog::textwrap::{UnbreakableTokens, Wrapper};
+//!
+//! let tokens = UnbreakableTokens::default().pattern(r"TICKET \d+").unwrap();
+//! let wrapper = Wrapper::new().width(20).tokens(tokens);
+//! assert_eq!(
+//!     wrapper.wrap("* Fix the frobnicator, TICKET 1234.", None, Some("  ")),
+//!     vec!["* Fix the", "  frobnicator,", "  TICKET 1234."]
+//! );
+//! ```
+
+use lazy_regex::{regex_captures, Regex};
 use std::borrow::Cow;
-use textwrap::core::Word;
+use std::ops::Range;
+use textwrap::core::{display_width, Word};
+use textwrap::WrapAlgorithm;
 
 pub const DEFAULT_WIDTH: usize = 78;
 pub const INITIAL_INDENT: &str = "* ";
 
-#[inline]
-fn can_break_word(line: &str, pos: usize) -> bool {
-    if let Some(_bugnp) = line.strip_prefix("Closes: #") {
-        if pos < line.find('#').unwrap() {
-            return false;
+/// A set of patterns that the wrapper never breaks a line inside of.
+///
+/// The default set protects bug closers ("Closes: #1, #2" and "LP: #1"), "Bug-Debian:"
+/// references, CVE identifiers, URLs, file paths and backquoted code.
+#[derive(Debug, Clone)]
+pub struct UnbreakableTokens {
+    patterns: Vec<Regex>,
+}
+
+impl Default for UnbreakableTokens {
+    fn default() -> Self {
+        Self {
+            patterns: vec![
+                Regex::clone(&crate::bugs::CLOSES),
+                Regex::clone(&crate::bugs::LAUNCHPAD_BUGS),
+                Regex::clone(lazy_regex::regex!(r"(?i)bug-debian:\s*\S+")),
+                Regex::clone(lazy_regex::regex!(r"\bCVE-\d{4}-\d{4,}\b")),
+                Regex::clone(lazy_regex::regex!(r"\bhttps?://bugs\.debian\.org/\S+")),
+                Regex::clone(lazy_regex::regex!(r"\b[a-zA-Z][a-zA-Z0-9+.-]*://\S+")),
+                Regex::clone(lazy_regex::regex!(r"[\w.+~-]*(?:/[\w.+~-]+)+/?")),
+                Regex::clone(lazy_regex::regex!(r"`[^`]*`")),
+            ],
         }
     }
-    if let Some(_lpbugno) = line.strip_prefix("LP: #") {
-        if pos < line.find('#').unwrap() {
-            return false;
-        }
+}
+
+impl UnbreakableTokens {
+    /// Create an empty set of patterns.
+    pub fn new() -> Self {
+        Self { patterns: vec![] }
+    }
+
+    /// Add a pattern, as a regular expression.
+    pub fn pattern(self, pattern: &str) -> Result<Self, lazy_regex::regex::Error> {
+        Ok(self.regex(Regex::new(pattern)?))
+    }
+
+    /// Add a compiled pattern.
+    pub fn regex(mut self, pattern: Regex) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Find the byte ranges of the tokens in a line.
+    pub fn find(&self, line: &str) -> Vec<Range<usize>> {
+        self.patterns
+            .iter()
+            .flat_map(|p| p.find_iter(line).map(|m| m.range()))
+            .collect()
     }
-    line[pos..].starts_with(' ')
+}
+
+#[inline]
+fn can_break_word(line: &str, pos: usize, protected: &[Range<usize>]) -> bool {
+    line[pos..].starts_with(' ') && !protected.iter().any(|r| r.start < pos && pos < r.end)
 }
 
 #[cfg(test)]
 mod can_break_word_tests {
+    fn can_break_word(line: &str, pos: usize) -> bool {
+        let protected = super::UnbreakableTokens::default().find(line);
+        super::can_break_word(line, pos, &protected)
+    }
+
     #[test]
     fn test_can_break_word() {
-        assert!(super::can_break_word("foo bar", 3));
-        assert!(!super::can_break_word("foo bar", 0));
-        assert!(!super::can_break_word("foo bar", 5));
+        assert!(can_break_word("foo bar", 3));
+        assert!(!can_break_word("foo bar", 0));
+        assert!(!can_break_word("foo bar", 5));
     }
 
     #[test]
     fn test_closes() {
-        assert!(!super::can_break_word("Closes: #123456", 6));
-        assert!(!super::can_break_word("Closes: #123456", 7));
-        assert!(!super::can_break_word("Closes: #123456", 8));
-        assert!(!super::can_break_word("Closes: #123456", 9));
-        assert!(super::can_break_word("Closes: #123456 foo", 15));
+        assert!(!can_break_word("Closes: #123456", 6));
+        assert!(!can_break_word("Closes: #123456", 7));
+        assert!(!can_break_word("Closes: #123456", 8));
+        assert!(!can_break_word("Closes: #123456", 9));
+        assert!(can_break_word("Closes: #123456 foo", 15));
+    }
+
+    #[test]
+    fn test_tokens() {
+        let line = "Fix `foo bar`, see Bug-Debian: https://bugs.debian.org/1 and Closes: #1, #2 ok";
+        let breaks = (0..line.len())
+            .filter(|i| can_break_word(line, *i))
+            .map(|i| &line[..i])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            breaks,
+            vec![
+                "Fix",
+                "Fix `foo bar`,",
+                "Fix `foo bar`, see",
+                "Fix `foo bar`, see Bug-Debian: https://bugs.debian.org/1",
+                "Fix `foo bar`, see Bug-Debian: https://bugs.debian.org/1 and",
+                "Fix `foo bar`, see Bug-Debian: https://bugs.debian.org/1 and Closes: #1, #2",
+            ]
+        );
     }
 }
 
-fn find_words<'a>(line: &'a str) -> Box<dyn Iterator<Item = Word<'a>> + 'a> {
+fn find_words<'a>(line: &'a str, tokens: &UnbreakableTokens) -> Vec<Word<'a>> {
+    let protected = tokens.find(line);
+    let mut words = vec![];
     let mut start = 0;
     let mut can_break = false;
-    let mut char_indices = line.char_indices();
-
-    Box::new(std::iter::from_fn(move || {
-        for (idx, ch) in char_indices.by_ref() {
-            let word_finished = can_break && ch != ' ';
-            can_break = can_break_word(&line[start..], idx - start);
-            if word_finished {
-                let word = Word::from(&line[start..idx]);
-                start = idx;
-                return Some(word);
-            }
-        }
 
-        if start < line.len() {
-            let word = Word::from(&line[start..]);
-            start = line.len();
-            return Some(word);
+    for (idx, ch) in line.char_indices() {
+        let word_finished = can_break && ch != ' ';
+        can_break = can_break_word(line, idx, &protected);
+        if word_finished {
+            words.push(Word::from(&line[start..idx]));
+            start = idx;
         }
+    }
 
-        None
-    }))
+    if start < line.len() {
+        words.push(Word::from(&line[start..]));
+    }
+
+    words
 }
 
 #[cfg(test)]
 mod find_words_tests {
-    use super::find_words;
+    use super::UnbreakableTokens;
     use textwrap::core::Word;
-    use textwrap::WordSeparator;
+
+    fn find_words(line: &str) -> Vec<Word<'_>> {
+        super::find_words(line, &UnbreakableTokens::default())
+    }
+
     #[test]
     fn test_find_words() {
-        let ws = WordSeparator::Custom(find_words);
-        assert_eq!(
-            vec![Word::from("foo")],
-            ws.find_words("foo").collect::<Vec<_>>()
-        );
+        assert_eq!(vec![Word::from("foo")], find_words("foo"));
         assert_eq!(
             vec![Word::from("foo "), Word::from("bar")],
-            ws.find_words("foo bar").collect::<Vec<_>>()
+            find_words("foo bar")
         );
     }
 
     #[test]
     fn test_split_closes() {
-        let ws = WordSeparator::Custom(find_words);
         assert_eq!(
             vec![
                 Word::from("This "),
@@ -99,8 +176,7 @@ mod find_words_tests {
                 Word::from("Closes: #123456 "),
                 Word::from("foo"),
             ],
-            ws.find_words("This test Closes: #123456 foo")
-                .collect::<Vec<_>>()
+            find_words("This test Closes: #123456 foo")
         );
 
         assert_eq!(
@@ -109,29 +185,165 @@ mod find_words_tests {
                 Word::from("test "),
                 Word::from("Closes: #123456"),
             ],
-            ws.find_words("This test Closes: #123456")
-                .collect::<Vec<_>>()
+            find_words("This test Closes: #123456")
+        );
+    }
+
+    #[test]
+    fn test_custom_tokens() {
+        let tokens = UnbreakableTokens::new().pattern(r"ACME-\d+ \w+").unwrap();
+        assert_eq!(
+            vec![
+                Word::from("See "),
+                Word::from("ACME-12 foo "),
+                Word::from("bar")
+            ],
+            super::find_words("See ACME-12 foo bar", &tokens)
+        );
+        assert_eq!(
+            vec![Word::from("Closes: "), Word::from("#1")],
+            super::find_words("Closes: #1", &tokens)
         );
     }
 }
 
-fn options<'a>(
-    width: Option<usize>,
-    initial_indent: Option<&'a str>,
-    subsequent_indent: Option<&'a str>,
-) -> textwrap::Options<'a> {
-    let width = width.unwrap_or(DEFAULT_WIDTH);
-    let mut options = textwrap::Options::new(width)
-        .break_words(false)
-        .word_splitter(textwrap::WordSplitter::NoHyphenation)
-        .word_separator(textwrap::WordSeparator::Custom(find_words));
-    if let Some(initial_indent) = initial_indent {
-        options = options.initial_indent(initial_indent);
-    }
-    if let Some(subsequent_indent) = subsequent_indent {
-        options = options.subsequent_indent(subsequent_indent);
-    }
-    options
+/// Wraps text to a width, without breaking unbreakable tokens.
+#[derive(Debug, Clone)]
+pub struct Wrapper {
+    width: usize,
+    tokens: UnbreakableTokens,
+}
+
+impl Default for Wrapper {
+    /// A wrapper for [`DEFAULT_WIDTH`] with the default unbreakable tokens.
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WIDTH,
+            tokens: UnbreakableTokens::default(),
+        }
+    }
+}
+
+impl Wrapper {
+    /// Create a wrapper for [`DEFAULT_WIDTH`] with the default unbreakable tokens.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the width to wrap to, in terminal columns.
+    ///
+    /// Changes can not be rewrapped to a width of 4 columns or less; rewrapping them fails with
+    /// [`Error::WidthTooSmall`].
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Set the tokens that are never broken.
+    pub fn tokens(mut self, tokens: UnbreakableTokens) -> Self {
+        self.tokens = tokens;
+        self
+    }
+
+    /// Wrap a string of text.
+    pub fn wrap<'a>(
+        &self,
+        text: &'a str,
+        initial_indent: Option<&str>,
+        subsequent_indent: Option<&str>,
+    ) -> Vec<Cow<'a, str>> {
+        let initial_indent = initial_indent.unwrap_or("");
+        let subsequent_indent = subsequent_indent.unwrap_or("");
+        let mut lines = Vec::new();
+        for line in text.split('\n') {
+            let indent = if lines.is_empty() {
+                initial_indent
+            } else {
+                subsequent_indent
+            };
+            if line.len() < self.width && indent.is_empty() {
+                lines.push(Cow::from(line.trim_end_matches(' ')));
+            } else {
+                self.wrap_line(line, initial_indent, subsequent_indent, &mut lines);
+            }
+        }
+        lines
+    }
+
+    fn wrap_line<'a>(
+        &self,
+        line: &'a str,
+        initial_indent: &str,
+        subsequent_indent: &str,
+        lines: &mut Vec<Cow<'a, str>>,
+    ) {
+        let line_widths = [
+            self.width.saturating_sub(display_width(initial_indent)),
+            self.width.saturating_sub(display_width(subsequent_indent)),
+        ];
+        let words = find_words(line, &self.tokens);
+        let mut idx = 0;
+        for words in WrapAlgorithm::new().wrap(&words, &line_widths) {
+            let Some(last_word) = words.last() else {
+                lines.push(Cow::from(""));
+                continue;
+            };
+            let len = words
+                .iter()
+                .map(|word| word.len() + word.whitespace.len())
+                .sum::<usize>()
+                - last_word.whitespace.len();
+            let indent = if lines.is_empty() {
+                initial_indent
+            } else {
+                subsequent_indent
+            };
+            let mut result = Cow::Owned(indent.to_owned());
+            result += &line[idx..idx + len];
+            lines.push(result);
+            idx += len + last_word.whitespace.len();
+        }
+    }
+
+    /// Rewrap lines from an iterator of changes.
+    ///
+    /// Blank lines and section headers (`[ Author ]`) are left alone.
+    ///
+    /// # Errors
+    /// Returns an error if a change does not start with a bullet point, or if a line of a
+    /// change that needs to be rewrapped is not indented to match the bullet point.
+    pub fn try_rewrap_changes<'a>(
+        &self,
+        changes: impl Iterator<Item = &'a str>,
+    ) -> Result<Vec<Cow<'a, str>>, Error> {
+        let mut ret = vec![];
+        for (rewrap, lines) in group_changes(changes) {
+            if rewrap {
+                ret.extend(rewrap_change(lines.as_slice(), self)?);
+            } else {
+                ret.extend(lines.into_iter().map(Cow::Borrowed));
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Rewrap lines from an iterator of changes.
+    ///
+    /// Unlike [`Wrapper::try_rewrap_changes`], changes that can not be rewrapped are left
+    /// untouched.
+    pub fn rewrap_changes_lenient<'a>(
+        &self,
+        changes: impl Iterator<Item = &'a str>,
+    ) -> Vec<Cow<'a, str>> {
+        group_changes(changes)
+            .into_iter()
+            .flat_map(|(rewrap, lines)| match rewrap {
+                true => rewrap_change(lines.as_slice(), self)
+                    .unwrap_or_else(|_| lines.into_iter().map(Cow::Borrowed).collect()),
+                false => lines.into_iter().map(Cow::Borrowed).collect(),
+            })
+            .collect()
+    }
 }
 
 /// Wrap a string of text, without breaking in between "Closes: #XXXXXX" fragments
@@ -141,9 +353,11 @@ pub fn textwrap<'a>(
     initial_indent: Option<&str>,
     subsequent_indent: Option<&str>,
 ) -> Vec<Cow<'a, str>> {
-    let options = options(width, initial_indent, subsequent_indent);
-    // Actual text wrapping using textwrap crate
-    textwrap::wrap(text, options)
+    Wrapper::new().width(width.unwrap_or(DEFAULT_WIDTH)).wrap(
+        text,
+        initial_indent,
+        subsequent_indent,
+    )
 }
 
 #[cfg(test)]
@@ -163,30 +377,44 @@ mod textwrap_tests {
 
     #[test]
     fn test_wrap() {
-        let ws = textwrap::WordSeparator::Custom(super::find_words);
-        let options = textwrap::Options::new(30)
-            .break_words(false)
-            .word_separator(ws);
         assert_eq!(
             vec!["This", "is", "a", "line", "that", "has", "been", "broken"],
-            ws.find_words("This is a line that has been broken")
-                .map(|w| w.to_string())
-                .collect::<Vec<_>>()
+            super::find_words(
+                "This is a line that has been broken",
+                &super::UnbreakableTokens::default()
+            )
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>()
         );
+
         assert_eq!(
             vec!["This is a line that has been", "broken"],
-            textwrap::wrap("This is a line that has been broken", options)
+            super::textwrap("This is a line that has been broken", Some(30), None, None)
         );
+    }
 
+    #[test]
+    fn test_wrap_tokens() {
         assert_eq!(
-            vec!["This is a line that has been", "broken"],
-            super::textwrap("This is a line that has been broken", Some(30), None, None)
+            vec![
+                "* Run",
+                "  `make check all`",
+                "  before uploading.",
+                "  Closes: #1, #2"
+            ],
+            super::textwrap(
+                "Run `make check all` before uploading. Closes: #1, #2",
+                Some(20),
+                Some("* "),
+                Some("  ")
+            )
         );
     }
 }
 
 // Checks if two lines can join
-fn can_join(line1: &str, line2: &str) -> bool {
+pub(crate) fn can_join(line1: &str, line2: &str) -> bool {
     if line1.ends_with(':') {
         return false;
     }
@@ -240,9 +468,9 @@ mod can_join_tests {
     }
 }
 
-// Check if any lines are longer than the specified width
+// Check if any lines are wider than the specified width, in terminal columns
 fn any_long_lines(lines: &[&str], width: usize) -> bool {
-    lines.iter().any(|line| line.len() > width)
+    lines.iter().any(|line| display_width(line) > width)
 }
 
 #[derive(Debug, PartialEq)]
@@ -255,6 +483,9 @@ pub enum Error {
         line: String,
         indent: usize,
     },
+    WidthTooSmall {
+        width: usize,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -272,6 +503,9 @@ impl std::fmt::Display for Error {
                 "Unexpected indent in line {}: {} (expected {} spaces)",
                 lineno, line, indent
             ),
+            Error::WidthTooSmall { width } => {
+                write!(f, "Width too small to wrap changes: {}", width)
+            }
         }
     }
 }
@@ -289,9 +523,12 @@ impl std::error::Error for Error {}
 // * This is a short line that
 //   needs to be wrappd
 //
-fn rewrap_change<'a>(change: &[&'a str], width: Option<usize>) -> Result<Vec<Cow<'a, str>>, Error> {
-    let width = width.unwrap_or(DEFAULT_WIDTH);
-    assert!(width > 4);
+fn rewrap_change<'a>(change: &[&'a str], wrapper: &Wrapper) -> Result<Vec<Cow<'a, str>>, Error> {
+    let width = wrapper.width;
+    // Leave room for at least a bullet, its indentation and a character of text
+    if width <= 4 {
+        return Err(Error::WidthTooSmall { width });
+    }
 
     if change.is_empty() {
         return Ok(vec![]);
@@ -305,21 +542,21 @@ fn rewrap_change<'a>(change: &[&'a str], width: Option<usize>) -> Result<Vec<Cow
             })
         }
     };
-    let prefix_len = initial_indent.len();
+    let prefix_width = display_width(initial_indent.as_str());
 
     if !any_long_lines(change, width) {
         return Ok(change.iter().map(|line| (*line).into()).collect());
     }
-    let mut subsequent_indent = " ".repeat(prefix_len);
+    let mut subsequent_indent = " ".repeat(prefix_width);
 
-    let mut lines = vec![&change[0][prefix_len..]];
+    let mut lines = vec![&change[0][initial_indent.len()..]];
 
     // Strip the leading indentation
     for (lineno, line) in change[1..].iter().enumerate() {
-        if line.len() < prefix_len {
-            lines.push(&line[0..0]);
-        } else if line.strip_prefix(subsequent_indent.as_str()).is_some() {
-            lines.push(&line[initial_indent.len()..]);
+        if line.trim().is_empty() {
+            lines.push("");
+        } else if let Some(rest) = line.strip_prefix(subsequent_indent.as_str()) {
+            lines.push(rest);
         } else {
             return Err(Error::UnexpectedIndent {
                 lineno,
@@ -337,68 +574,104 @@ fn rewrap_change<'a>(change: &[&'a str], width: Option<usize>) -> Result<Vec<Cow
             todo.push(line);
         } else {
             ret.extend(
-                textwrap(
-                    todo.join(" ").as_str(),
-                    Some(width),
-                    Some(initial_indent.as_str()),
-                    Some(subsequent_indent.as_str()),
-                )
-                .iter()
-                .map(|s| Cow::Owned(s.to_string())),
+                wrapper
+                    .wrap(
+                        todo.join(" ").as_str(),
+                        Some(initial_indent.as_str()),
+                        Some(subsequent_indent.as_str()),
+                    )
+                    .iter()
+                    .map(|s| Cow::Owned(s.to_string())),
             );
             initial_indent =
-                " ".repeat(prefix_len + line.len() - line.trim_start_matches(' ').len());
+                " ".repeat(prefix_width + line.len() - line.trim_start_matches(' ').len());
             subsequent_indent = " ".repeat(initial_indent.len());
             todo = vec![line.trim_start_matches(' ')];
         }
     }
     ret.extend(
-        textwrap(
-            todo.join(" ").as_str(),
-            Some(width),
-            Some(initial_indent.as_str()),
-            Some(subsequent_indent.as_str()),
-        )
-        .iter()
-        .map(|s| Cow::Owned(s.to_string())),
+        wrapper
+            .wrap(
+                todo.join(" ").as_str(),
+                Some(initial_indent.as_str()),
+                Some(subsequent_indent.as_str()),
+            )
+            .iter()
+            .map(|s| Cow::Owned(s.to_string())),
     );
     Ok(ret)
 }
 
-// Rewrap lines from an iterator of changes
-pub fn rewrap_changes<'a>(
+/// Split lines into groups that are rewrapped together.
+///
+/// Each bulleted change forms a group with the lines that follow it, up to the next bullet,
+/// blank line or section header (`[ Author ]`). Blank lines and section headers are returned
+/// on their own, with `false` to indicate that they should be left alone.
+pub(crate) fn group_changes<'a>(
     changes: impl Iterator<Item = &'a str>,
-) -> impl Iterator<Item = Cow<'a, str>> {
-    let mut change = Vec::new();
-    let mut indent_len: Option<usize> = None;
-    let mut ret = vec![];
+) -> Vec<(bool, Vec<&'a str>)> {
+    let mut groups: Vec<(bool, Vec<&'a str>)> = vec![];
+    let mut in_change = false;
     for line in changes {
-        // Start of a new change
-        if let Some(indent) = regex_captures!(r"^[  ]*[\+\-\*] ", line) {
-            ret.extend(rewrap_change(change.as_slice(), None).unwrap());
-            indent_len = Some(indent.len());
-            change = vec![line];
-        } else if let Some(current_indent) = indent_len {
-            if line.starts_with(&" ".repeat(current_indent)) {
-                change.push(line[current_indent..].into());
-            } else {
-                ret.extend(rewrap_change(change.as_slice(), None).unwrap());
-                change = vec![line];
-            }
+        if line.trim().is_empty() || regex_captures!(r"^\[ .* \]$", line).is_some() {
+            groups.push((false, vec![line]));
+            in_change = false;
+        } else if regex_captures!(r"^[  ]*[\+\-\*] ", line).is_some() || !in_change {
+            groups.push((true, vec![line]));
+            in_change = true;
         } else {
-            ret.extend(rewrap_change(change.as_slice(), None).unwrap());
-            ret.push(line.into());
+            groups.last_mut().unwrap().1.push(line);
         }
     }
-    if !change.is_empty() {
-        ret.extend(rewrap_change(change.as_slice(), None).unwrap());
-    }
-    ret.into_iter()
+    groups
+}
+
+/// Rewrap lines from an iterator of changes, to the given width.
+///
+/// Blank lines and section headers (`[ Author ]`) are left alone. See
+/// [`Wrapper::try_rewrap_changes`].
+pub fn try_rewrap_changes<'a>(
+    changes: impl Iterator<Item = &'a str>,
+    width: Option<usize>,
+) -> Result<Vec<Cow<'a, str>>, Error> {
+    Wrapper::new()
+        .width(width.unwrap_or(DEFAULT_WIDTH))
+        .try_rewrap_changes(changes)
+}
+
+/// Rewrap lines from an iterator of changes, to the given width.
+///
+/// Unlike [`try_rewrap_changes`], changes that can not be rewrapped are left untouched.
+pub fn rewrap_changes_lenient<'a>(
+    changes: impl Iterator<Item = &'a str>,
+    width: Option<usize>,
+) -> Vec<Cow<'a, str>> {
+    Wrapper::new()
+        .width(width.unwrap_or(DEFAULT_WIDTH))
+        .rewrap_changes_lenient(changes)
+}
+
+/// Rewrap lines from an iterator of changes, to [`DEFAULT_WIDTH`].
+///
+/// Changes that can not be rewrapped are left untouched.
+pub fn rewrap_changes<'a>(
+    changes: impl Iterator<Item = &'a str>,
+) -> impl Iterator<Item = Cow<'a, str>> {
+    rewrap_changes_lenient(changes, None).into_iter()
 }
 
 #[cfg(test)]
 mod rewrap_tests {
-    use super::rewrap_change;
+    use super::{rewrap_changes, rewrap_changes_lenient, try_rewrap_changes};
+    use std::borrow::Cow;
+
+    fn rewrap_change<'a>(
+        change: &[&'a str],
+        width: Option<usize>,
+    ) -> Result<Vec<Cow<'a, str>>, super::Error> {
+        let wrapper = super::Wrapper::new().width(width.unwrap_or(super::DEFAULT_WIDTH));
+        super::rewrap_change(change, &wrapper)
+    }
     const LONG_LINE: &str = "This is a very long line that could have been broken and should have been broken but was not broken.";
 
     #[test]
@@ -456,4 +729,164 @@ mod rewrap_tests {
       Currently text, newt, slang and gtk frontends support this feature.
 "###.split('\n').collect::<Vec<_>>().as_slice(), None).unwrap());
     }
+
+    #[test]
+    fn test_rewrap_changes_continuation() {
+        assert_eq!(
+            vec![
+                "* This is a very long line that could have been broken and should have been",
+                "  broken but was not broken. (Closes: #123)",
+            ],
+            rewrap_changes([format!("* {}", LONG_LINE).as_str(), "  (Closes: #123)"].into_iter())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_rewrap_changes_sections() {
+        let long = format!("* {}", LONG_LINE);
+        let lines = ["[ Joe ]", long.as_str(), "", "[ Jane ]", "* Short."];
+        assert_eq!(
+            vec![
+                "[ Joe ]",
+                "* This is a very long line that could have been broken and should have been",
+                "  broken but was not broken.",
+                "",
+                "[ Jane ]",
+                "* Short.",
+            ],
+            try_rewrap_changes(lines.into_iter(), None).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rewrap_changes_width() {
+        assert_eq!(
+            vec![
+                "* This is a very long",
+                "  line that could have",
+                "  been broken."
+            ],
+            try_rewrap_changes(
+                ["* This is a very long line that could have been broken."].into_iter(),
+                Some(22)
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rewrap_changes_errors() {
+        let long = format!("No bullet: {}", LONG_LINE);
+        assert_eq!(
+            super::Error::MissingBulletPoint { line: long.clone() },
+            try_rewrap_changes([long.as_str()].into_iter(), None).unwrap_err()
+        );
+        assert_eq!(
+            vec![long.as_str()],
+            rewrap_changes_lenient([long.as_str()].into_iter(), None)
+        );
+
+        let lines = ["  * Short.", LONG_LINE, "* Next."];
+        assert_eq!(
+            super::Error::UnexpectedIndent {
+                lineno: 0,
+                line: LONG_LINE.to_string(),
+                indent: 4
+            },
+            try_rewrap_changes(lines.into_iter(), None).unwrap_err()
+        );
+        assert_eq!(
+            lines.to_vec(),
+            rewrap_changes(lines.into_iter()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_width_too_small() {
+        let line = format!("* {}", LONG_LINE);
+        for width in 0..=4 {
+            assert_eq!(
+                super::Error::WidthTooSmall { width },
+                rewrap_change(&[line.as_str()], Some(width)).unwrap_err()
+            );
+            assert_eq!(
+                super::Error::WidthTooSmall { width },
+                try_rewrap_changes([line.as_str()].into_iter(), Some(width)).unwrap_err()
+            );
+            assert_eq!(
+                vec![line.as_str()],
+                rewrap_changes_lenient([line.as_str()].into_iter(), Some(width))
+            );
+            assert!(!super::Wrapper::new()
+                .width(width)
+                .wrap(LONG_LINE, Some("* "), Some("  "))
+                .is_empty());
+        }
+        assert!(rewrap_change(&[line.as_str()], Some(5)).is_ok());
+    }
+
+    #[test]
+    fn test_display_width() {
+        // 78 columns, but many more bytes
+        let line = format!("* Thanks to Jelmer Vernooĳ {}", "ĳ".repeat(51));
+        assert_eq!(
+            vec![line.as_str()],
+            rewrap_change(&[line.as_str()], None).unwrap()
+        );
+
+        // Wide characters take up two columns each
+        let line = format!("* {}", ["漢字"; 20].join(" "));
+        let wrapped = rewrap_change(&[line.as_str()], Some(20)).unwrap();
+        assert_eq!(
+            vec!["* 漢字 漢字 漢字", "  漢字 漢字 漢字"],
+            wrapped[..2].to_vec()
+        );
+        assert!(wrapped
+            .iter()
+            .all(|l| textwrap::core::display_width(l) <= 20));
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::{rewrap_changes, rewrap_changes_lenient, Wrapper};
+    use proptest::prelude::*;
+    use textwrap::core::display_width;
+
+    proptest! {
+        #[test]
+        fn rewrap_change_does_not_panic(
+            lines in prop::collection::vec(any::<String>(), 0..5),
+            width in 0usize..100,
+        ) {
+            let lines = lines.iter().map(|l| l.as_str()).collect::<Vec<_>>();
+            let _ = super::rewrap_change(&lines, &Wrapper::new().width(width));
+        }
+
+        #[test]
+        fn rewrap_change_with_bullet_does_not_panic(
+            first in "\\PC*",
+            rest in prop::collection::vec("[ \u{a0}\u{3000}]{0,4}\\PC*", 0..5),
+            width in 0usize..100,
+        ) {
+            let mut lines = vec![format!("* {}", first)];
+            lines.extend(rest.into_iter().map(|l| format!("  {}", l)));
+            let lines = lines.iter().map(|l| l.as_str()).collect::<Vec<_>>();
+            let _ = super::rewrap_change(&lines, &Wrapper::new().width(width));
+            let _ = rewrap_changes_lenient(lines.iter().copied(), Some(width));
+            let _ = rewrap_changes(lines.into_iter()).count();
+        }
+
+        #[test]
+        fn wrapped_lines_fit(
+            words in prop::collection::vec("[\\p{L}\\p{Han}]{1,8}", 1..30),
+            width in 20usize..80,
+        ) {
+            let line = format!("* {}", words.join(" "));
+            for wrapped in super::rewrap_change(&[line.as_str()], &Wrapper::new().width(width)).unwrap() {
+                prop_assert!(display_width(&wrapped) <= width, "{:?}", wrapped);
+            }
+        }
+    }
 }
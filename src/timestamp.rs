@@ -0,0 +1,373 @@
+//! Lenient parsing of the timestamps in changelog trailers.
+//!
+//! The canonical format is `%a, %d %b %Y %H:%M:%S %z`, but many older changelogs deviate from
+//! it in ways that dpkg tolerates. The parser here accepts those and reports what it accepted.
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::timestamp::{parse, Deviation};
+//!
+//! let timestamp = parse("Mon,  4 September 2023 18:13:45 GMT").unwrap();
+//! assert_eq!(
+//!     timestamp.deviations,
+//!     vec![
+//!         Deviation::ExtraWhitespace,
+//!         Deviation::SingleDigitDay,
+//!         Deviation::FullMonthName,
+//!         Deviation::NamedTimezone("GMT".to_string()),
+//!     ]
+//! );
+//! assert_eq!(timestamp.to_string(), "Mon, 04 Sep 2023 18:13:45 +0000");
+//! ```
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveTime, TimeZone, Weekday};
+
+const WEEKDAYS: [(&str, &str, Weekday); 7] = [
+    ("Mon", "Monday", Weekday::Mon),
+    ("Tue", "Tuesday", Weekday::Tue),
+    ("Wed", "Wednesday", Weekday::Wed),
+    ("Thu", "Thursday", Weekday::Thu),
+    ("Fri", "Friday", Weekday::Fri),
+    ("Sat", "Saturday", Weekday::Sat),
+    ("Sun", "Sunday", Weekday::Sun),
+];
+
+const MONTHS: [(&str, &str); 12] = [
+    ("Jan", "January"),
+    ("Feb", "February"),
+    ("Mar", "March"),
+    ("Apr", "April"),
+    ("May", "May"),
+    ("Jun", "June"),
+    ("Jul", "July"),
+    ("Aug", "August"),
+    ("Sep", "September"),
+    ("Oct", "October"),
+    ("Nov", "November"),
+    ("Dec", "December"),
+];
+
+/// Time zone names accepted by RFC 2822, with their offset in hours.
+const TIMEZONES: [(&str, i32); 12] = [
+    ("GMT", 0),
+    ("UTC", 0),
+    ("UT", 0),
+    ("Z", 0),
+    ("EST", -5),
+    ("EDT", -4),
+    ("CST", -6),
+    ("CDT", -5),
+    ("MST", -7),
+    ("MDT", -6),
+    ("PST", -8),
+    ("PDT", -7),
+];
+
+/// A way in which a timestamp deviates from the canonical format.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Deviation {
+    /// The components are separated by more than a single space, or there is
+    /// leading or trailing whitespace.
+    ExtraWhitespace,
+    /// The day of the week is missing.
+    MissingWeekday,
+    /// The day of the week is not followed by a comma.
+    MissingComma,
+    /// The day of the week does not match the date.
+    WrongWeekday {
+        /// The day of the week in the timestamp.
+        found: Weekday,
+        /// The actual day of the week of the date.
+        expected: Weekday,
+    },
+    /// The day of the week is spelled out, e.g. "Monday".
+    FullWeekdayName,
+    /// The day of the month has a single digit, e.g. "4" rather than "04".
+    SingleDigitDay,
+    /// The month is spelled out, e.g. "September".
+    FullMonthName,
+    /// The seconds are missing from the time.
+    MissingSeconds,
+    /// The time zone is a name, e.g. "GMT", rather than a numeric offset.
+    NamedTimezone(String),
+}
+
+impl std::fmt::Display for Deviation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Deviation::ExtraWhitespace => write!(f, "extra whitespace"),
+            Deviation::MissingWeekday => write!(f, "missing day of the week"),
+            Deviation::MissingComma => write!(f, "missing comma after the day of the week"),
+            Deviation::WrongWeekday { found, expected } => {
+                write!(f, "day of the week is {} rather than {}", found, expected)
+            }
+            Deviation::FullWeekdayName => write!(f, "full instead of abbreviated day name"),
+            Deviation::SingleDigitDay => write!(f, "single digit day of the month"),
+            Deviation::FullMonthName => write!(f, "full instead of abbreviated month name"),
+            Deviation::MissingSeconds => write!(f, "missing seconds"),
+            Deviation::NamedTimezone(name) => {
+                write!(f, "time zone name {} instead of numeric offset", name)
+            }
+        }
+    }
+}
+
+/// An error parsing a timestamp.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A component of the timestamp is missing, e.g. the time zone.
+    Missing(&'static str),
+    /// A component of the timestamp could not be parsed.
+    Invalid {
+        component: &'static str,
+        value: String,
+    },
+    /// There is unexpected text after the timestamp.
+    TrailingText(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Missing(component) => write!(f, "missing {}", component),
+            Error::Invalid { component, value } => write!(f, "invalid {}: {}", component, value),
+            Error::TrailingText(text) => write!(f, "unexpected text after timestamp: {}", text),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A parsed timestamp, with the deviations from the canonical format that were accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timestamp {
+    pub datetime: DateTime<FixedOffset>,
+    pub deviations: Vec<Deviation>,
+}
+
+impl Timestamp {
+    /// Return whether the timestamp was in the canonical format.
+    pub fn is_canonical(&self) -> bool {
+        self.deviations.is_empty()
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    /// Format the timestamp in the canonical format.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.datetime.format(crate::parse::CHANGELOG_TIME_FORMAT)
+        )
+    }
+}
+
+fn invalid(component: &'static str, value: &str) -> Error {
+    Error::Invalid {
+        component,
+        value: value.to_string(),
+    }
+}
+
+fn parse_offset(zone: &str) -> Option<i32> {
+    let (sign, digits) = match zone.as_bytes().first()? {
+        b'+' => (1, &zone[1..]),
+        b'-' => (-1, &zone[1..]),
+        _ => return None,
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours = digits[..2].parse::<i32>().ok()?;
+    let minutes = digits[2..].parse::<i32>().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Parse a timestamp, accepting the deviations from the canonical format that dpkg accepts.
+pub fn parse(text: &str) -> Result<Timestamp, Error> {
+    let mut deviations = vec![];
+    let mut tokens = text.split_whitespace().peekable();
+    if tokens.clone().collect::<Vec<_>>().join(" ") != text {
+        deviations.push(Deviation::ExtraWhitespace);
+    }
+
+    let mut weekday = None;
+    let first = *tokens.peek().ok_or(Error::Missing("date"))?;
+    if first.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        tokens.next();
+        let name = match first.strip_suffix(',') {
+            Some(name) => name,
+            None => {
+                deviations.push(Deviation::MissingComma);
+                first
+            }
+        };
+        let (_, full, day) = WEEKDAYS
+            .iter()
+            .find(|(abbrev, full, _)| {
+                name.eq_ignore_ascii_case(abbrev) || name.eq_ignore_ascii_case(full)
+            })
+            .ok_or_else(|| invalid("day of the week", first))?;
+        if name.len() == full.len() && name.len() > 3 {
+            deviations.push(Deviation::FullWeekdayName);
+        }
+        weekday = Some(*day);
+    } else {
+        deviations.push(Deviation::MissingWeekday);
+    }
+
+    let day = tokens.next().ok_or(Error::Missing("day of the month"))?;
+    if day.len() > 2 || !day.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid("day of the month", day));
+    }
+    if day.len() == 1 {
+        deviations.push(Deviation::SingleDigitDay);
+    }
+
+    let month = tokens.next().ok_or(Error::Missing("month"))?;
+    let month_index = MONTHS
+        .iter()
+        .position(|(abbrev, full)| {
+            month.eq_ignore_ascii_case(abbrev) || month.eq_ignore_ascii_case(full)
+        })
+        .ok_or_else(|| invalid("month", month))?;
+    if month.len() > 3 {
+        deviations.push(Deviation::FullMonthName);
+    }
+
+    let year = tokens.next().ok_or(Error::Missing("year"))?;
+    if year.len() != 4 || !year.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid("year", year));
+    }
+
+    let date = NaiveDate::from_ymd_opt(
+        year.parse().unwrap(),
+        month_index as u32 + 1,
+        day.parse().unwrap(),
+    )
+    .ok_or_else(|| invalid("date", &format!("{} {} {}", day, month, year)))?;
+
+    let time = tokens.next().ok_or(Error::Missing("time"))?;
+    let time = match NaiveTime::parse_from_str(time, "%H:%M:%S") {
+        Ok(time) => time,
+        Err(_) => {
+            let time =
+                NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| invalid("time", time))?;
+            deviations.push(Deviation::MissingSeconds);
+            time
+        }
+    };
+
+    let zone = tokens.next().ok_or(Error::Missing("time zone"))?;
+    let offset = match parse_offset(zone) {
+        Some(offset) => offset,
+        None => {
+            let (name, hours) = TIMEZONES
+                .iter()
+                .find(|(name, _)| zone.eq_ignore_ascii_case(name))
+                .ok_or_else(|| invalid("time zone", zone))?;
+            deviations.push(Deviation::NamedTimezone(name.to_string()));
+            hours * 3600
+        }
+    };
+    let offset = FixedOffset::east_opt(offset).ok_or_else(|| invalid("time zone", zone))?;
+
+    let rest = tokens.collect::<Vec<_>>();
+    if !rest.is_empty() {
+        return Err(Error::TrailingText(rest.join(" ")));
+    }
+
+    let datetime = offset
+        .from_local_datetime(&date.and_time(time))
+        .single()
+        .ok_or_else(|| invalid("time", text))?;
+
+    if let Some(found) = weekday {
+        let expected = date.weekday();
+        if found != expected {
+            deviations.push(Deviation::WrongWeekday { found, expected });
+        }
+    }
+
+    Ok(Timestamp {
+        datetime,
+        deviations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deviations(text: &str) -> Vec<Deviation> {
+        parse(text).unwrap().deviations
+    }
+
+    #[test]
+    fn test_canonical() {
+        let timestamp = parse("Mon, 04 Sep 2023 18:13:45 -0500").unwrap();
+        assert!(timestamp.is_canonical());
+        assert_eq!(
+            timestamp.datetime,
+            DateTime::parse_from_rfc3339("2023-09-04T18:13:45-05:00").unwrap()
+        );
+        assert_eq!(timestamp.to_string(), "Mon, 04 Sep 2023 18:13:45 -0500");
+    }
+
+    #[test]
+    fn test_deviations() {
+        assert_eq!(
+            deviations("04 Sep 2023 18:13:45 -0500"),
+            vec![Deviation::MissingWeekday]
+        );
+        assert_eq!(
+            deviations("Tue, 04 Sep 2023 18:13:45 -0500"),
+            vec![Deviation::WrongWeekday {
+                found: Weekday::Tue,
+                expected: Weekday::Mon,
+            }]
+        );
+        assert_eq!(
+            deviations("Monday 04 Sep 2023 18:13 UTC"),
+            vec![
+                Deviation::MissingComma,
+                Deviation::FullWeekdayName,
+                Deviation::MissingSeconds,
+                Deviation::NamedTimezone("UTC".to_string()),
+            ]
+        );
+        assert_eq!(
+            deviations(" Mon, 04 Sep 2023 18:13:45 -0500"),
+            vec![Deviation::ExtraWhitespace]
+        );
+        assert_eq!(
+            parse("Mon, 04 Sep 2023 18:13:45 EDT").unwrap().to_string(),
+            "Mon, 04 Sep 2023 18:13:45 -0400"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse(""), Err(Error::Missing("date")));
+        assert_eq!(
+            parse("Mon, 04 Sep 2023 18:13:45"),
+            Err(Error::Missing("time zone"))
+        );
+        assert_eq!(
+            parse("Mon, 31 Sep 2023 18:13:45 +0000"),
+            Err(invalid("date", "31 Sep 2023"))
+        );
+        assert_eq!(
+            parse("Mon, 04 Sep 2023 18:13:45 +0000 extra"),
+            Err(Error::TrailingText("extra".to_string()))
+        );
+        assert_eq!(
+            parse("Mon, 04 Sep 2023 18:13:45 XYZ")
+                .unwrap_err()
+                .to_string(),
+            "invalid time zone: XYZ"
+        );
+    }
+}
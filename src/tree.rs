@@ -0,0 +1,885 @@
+//! A typed model of the changes in the body of an entry.
+//!
+//! The body of an entry is split into sections by author (`[ Author ]`), which contain
+//! top-level changes (usually starting with "*"), which in turn can contain nested changes
+//! (usually starting with "+" or "-"). Each change refers to the lines of the entry it was
+//! read from ([`Change::syntax`]), so it can be edited, moved or removed without touching
+//! the rest of the entry.
+//!
+//! Sections can be merged, sorted and dropped in the same way, see
+//! [`Entry::merge_sections`], [`Entry::sort_sections`] and [`Entry::drop_empty_sections`].
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::ChangeLog;
+//!
+//! let changelog: ChangeLog = r#"foo (1.0-1) unstable; urgency=low
+//!
+//!   [ Jane Example ]
+//!   * Fix a bug.
+//!   * Fix a crash.
+//!     + With a test.
+//!
+//!  -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+//! "#.parse().unwrap();
+//! let entry = changelog.entries().next().unwrap();
+//! let sections = entry.sections();
+//! assert_eq!(sections[0].author().as_deref(), Some("Jane Example"));
+//! assert_eq!(sections[0].changes()[1].children()[0].lines(), vec!["  + With a test."]);
+//!
+//! sections[0].changes()[1].move_before(&sections[0].changes()[0]);
+//! assert_eq!(
+//!     entry.change_lines().collect::<Vec<_>>(),
+//!     vec!["[ Jane Example ]", "* Fix a crash.", "  + With a test.", "* Fix a bug."]
+//! );
+//! ```
+
+use crate::changes::{format_section_title, Bullet};
+use crate::parse::{new_node, new_token, EntryBody, SyntaxNode};
+use crate::Entry;
+use crate::SyntaxKind::*;
+use lazy_regex::regex_captures;
+use rowan::ast::AstNode;
+
+/// The changes by a single author.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    header: Option<SyntaxNode>,
+    changes: Vec<Change>,
+    // The header and all lines up to the next section, except for trailing blank lines
+    nodes: Vec<SyntaxNode>,
+}
+
+impl Section {
+    /// Return the author of the section, or None for changes before the first section
+    /// header.
+    pub fn author(&self) -> Option<String> {
+        let header = EntryBody::cast(self.header.clone()?)?.text();
+        regex_captures!(r"^\[ (.*) \]$", header.as_str()).map(|(_, author)| author.to_string())
+    }
+
+    /// Return the section header, or None for changes before the first section header.
+    pub fn header(&self) -> Option<EntryBody> {
+        EntryBody::cast(self.header.clone()?)
+    }
+
+    /// Return the top-level changes in the section.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+}
+
+/// A single change, with the changes nested below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    // The ENTRY_BODY nodes of the change itself, in order
+    lines: Vec<SyntaxNode>,
+    children: Vec<Change>,
+}
+
+impl Change {
+    /// Return the bullet the change starts with, if any.
+    pub fn bullet(&self) -> Option<Bullet> {
+        let line = self.first_line();
+        let (_, bullet) = regex_captures!(r"^ *([\+\-\*]) ", line.as_str())?;
+        Bullet::from_char(bullet.chars().next()?)
+    }
+
+    /// Return the number of columns before the bullet.
+    pub fn indent(&self) -> usize {
+        let line = self.first_line();
+        line.len() - line.trim_start_matches(' ').len()
+    }
+
+    /// Return the lines of the change itself, without the lines of nested changes.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.iter().map(line_text).collect()
+    }
+
+    /// Return the changes nested below this one.
+    pub fn children(&self) -> &[Change] {
+        &self.children
+    }
+
+    /// Return the ENTRY_BODY nodes of the change itself, in order, without the nodes of
+    /// nested changes.
+    pub fn syntax(&self) -> &[SyntaxNode] {
+        &self.lines
+    }
+
+    /// Replace the lines of the change, leaving nested changes alone.
+    ///
+    /// Each line replaces the old line at the same position, so lines that follow nested
+    /// changes stay after them. Extra lines are added after the last old line, and old lines
+    /// without a replacement are removed.
+    ///
+    /// # Panics
+    /// Panics if `lines` is empty.
+    pub fn replace_lines(&mut self, lines: &[&str]) {
+        assert!(!lines.is_empty(), "a change needs at least one line");
+        let new_lines = lines.iter().map(|line| body_line(line)).collect::<Vec<_>>();
+        for (old, new) in self.lines.iter().zip(&new_lines) {
+            let (parent, index) = (old.parent().unwrap(), old.index());
+            parent.splice_children(index..index + 1, vec![new.clone().into()]);
+        }
+        if new_lines.len() > self.lines.len() {
+            insert_at(
+                &new_lines[self.lines.len() - 1],
+                1,
+                new_lines[self.lines.len()..].to_vec(),
+            );
+        }
+        for old in self.lines.iter().skip(new_lines.len()) {
+            old.detach();
+        }
+        self.lines = new_lines;
+    }
+
+    /// Remove the change from the entry, along with the changes nested below it.
+    pub fn remove(self) {
+        for node in self.nodes() {
+            node.detach();
+        }
+    }
+
+    /// Move the change, along with the changes nested below it, to just before another
+    /// change.
+    ///
+    /// Moving a change next to one of the changes nested below it does nothing.
+    pub fn move_before(&self, other: &Change) {
+        let target = other.nodes().remove(0);
+        self.move_to(&target, 0);
+    }
+
+    /// Move the change, along with the changes nested below it, to just after another
+    /// change and the changes nested below that.
+    ///
+    /// Moving a change next to one of the changes nested below it does nothing.
+    pub fn move_after(&self, other: &Change) {
+        let target = other.nodes().pop().unwrap();
+        self.move_to(&target, 1);
+    }
+
+    fn move_to(&self, target: &SyntaxNode, offset: usize) {
+        let nodes = self.nodes();
+        if nodes.contains(target) {
+            return;
+        }
+        for node in &nodes {
+            node.detach();
+        }
+        insert_at(target, offset, nodes);
+    }
+
+    /// Return the nodes of the change and the changes nested below it, in order.
+    pub(crate) fn nodes(&self) -> Vec<SyntaxNode> {
+        let mut nodes = self.lines.clone();
+        nodes.extend(self.children.iter().flat_map(|c| c.nodes()));
+        nodes.sort_by_key(|n| n.text_range().start());
+        nodes
+    }
+
+    // Remove up to `columns` spaces from the start of the lines of the change and the
+    // changes nested below it.
+    fn unindent(&self, columns: usize) {
+        if columns == 0 {
+            return;
+        }
+        for node in self.nodes() {
+            let Some(detail) = node
+                .children_with_tokens()
+                .filter_map(|it| it.into_token())
+                .find(|t| t.kind() == DETAIL)
+            else {
+                continue;
+            };
+            let text = detail.text();
+            let spaces = (text.len() - text.trim_start_matches(' ').len()).min(columns);
+            let index = detail.index();
+            node.splice_children(index..index + 1, vec![new_token(DETAIL, &text[spaces..])]);
+        }
+    }
+
+    // Replace the bullet of the change, if it has one.
+    fn set_bullet(&self, bullet: Bullet) {
+        let Some(detail) = self.lines[0]
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|t| t.kind() == DETAIL)
+        else {
+            return;
+        };
+        let text = detail.text();
+        let Some((_, indent, _, rest)) = regex_captures!(r"^( *)([\+\-\*])( .*)$", text) else {
+            return;
+        };
+        let text = format!("{}{}{}", indent, bullet.as_char(), rest);
+        let index = detail.index();
+        self.lines[0].splice_children(index..index + 1, vec![new_token(DETAIL, &text)]);
+    }
+
+    fn first_line(&self) -> String {
+        line_text(&self.lines[0])
+    }
+}
+
+fn line_text(node: &SyntaxNode) -> String {
+    EntryBody::cast(node.clone())
+        .map(|b| b.text())
+        .unwrap_or_default()
+}
+
+fn is_blank(node: &SyntaxNode) -> bool {
+    node.kind() == EMPTY_LINE || (node.kind() == ENTRY_BODY && line_text(node).trim().is_empty())
+}
+
+// Create a body line, for a line of changes without the indentation. An empty line becomes
+// an EMPTY_LINE node, as the parser would produce.
+fn body_line(line: &str) -> SyntaxNode {
+    let kind = if line.is_empty() {
+        EMPTY_LINE
+    } else {
+        ENTRY_BODY
+    };
+    new_node(kind, |builder| {
+        if !line.is_empty() {
+            builder.token(INDENT.into(), "  ");
+            builder.token(DETAIL.into(), line);
+        }
+        builder.token(NEWLINE.into(), "\n");
+    })
+    .into_node()
+    .unwrap()
+}
+
+// Insert nodes before a sibling (offset 0) or after it (offset 1).
+fn insert_at(sibling: &SyntaxNode, offset: usize, nodes: Vec<SyntaxNode>) {
+    let index = sibling.index() + offset;
+    sibling
+        .parent()
+        .unwrap()
+        .splice_children(index..index, nodes.into_iter().map(|n| n.into()).collect());
+}
+
+// Remove a section header, along with the blank line that separates it from the previous
+// section (or from the next one, if there is no blank line before it).
+fn remove_header(header: &SyntaxNode) {
+    let separator = header
+        .prev_sibling()
+        .filter(is_blank)
+        .or_else(|| header.next_sibling().filter(is_blank));
+    header.detach();
+    if let Some(separator) = separator {
+        separator.detach();
+    }
+}
+
+// Return the list of changes at the given depth below the last change in a list.
+fn changes_at_depth(changes: &mut Vec<Change>, depth: usize) -> &mut Vec<Change> {
+    match depth {
+        0 => changes,
+        _ => changes_at_depth(&mut changes.last_mut().unwrap().children, depth - 1),
+    }
+}
+
+impl Entry {
+    /// Return the changes in the body of the entry, by author.
+    ///
+    /// Changes before the first section header are returned in a section without an
+    /// author, if there are any.
+    pub fn sections(&self) -> Vec<Section> {
+        let mut sections = vec![Section {
+            header: None,
+            changes: vec![],
+            nodes: vec![],
+        }];
+        // The changes that following lines can belong to, as (column of the text, has bullet)
+        let mut open: Vec<(usize, bool)> = vec![];
+        // Blank lines that will be part of a change if it continues after them
+        let mut blank = vec![];
+
+        for node in self.syntax().children() {
+            if node.kind() == EMPTY_LINE {
+                blank.push(node);
+                continue;
+            }
+            if node.kind() != ENTRY_BODY {
+                continue;
+            }
+            let line = line_text(&node);
+            if line.trim().is_empty() {
+                blank.push(node);
+                continue;
+            }
+            if regex_captures!(r"^\[ .* \]$", line.as_str()).is_some() {
+                sections.push(Section {
+                    header: Some(node.clone()),
+                    changes: vec![],
+                    nodes: vec![node],
+                });
+                open.clear();
+                blank.clear();
+                continue;
+            }
+
+            let section = sections.last_mut().unwrap();
+            if !section.nodes.is_empty() {
+                section.nodes.extend(blank.iter().cloned());
+            }
+            section.nodes.push(node.clone());
+            let changes = &mut section.changes;
+
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            let has_bullet = regex_captures!(r"^ *[\+\-\*] ", line.as_str()).is_some();
+            while open
+                .last()
+                .is_some_and(|(column, bulleted)| *column > indent || (has_bullet && !bulleted))
+            {
+                open.pop();
+            }
+            if has_bullet || open.is_empty() {
+                changes_at_depth(changes, open.len()).push(Change {
+                    lines: vec![node],
+                    children: vec![],
+                });
+                open.push((if has_bullet { indent + 2 } else { indent }, has_bullet));
+                blank.clear();
+            } else {
+                let siblings = changes_at_depth(changes, open.len() - 1);
+                let change = siblings.last_mut().unwrap();
+                change.lines.append(&mut blank);
+                change.lines.push(node);
+            }
+        }
+
+        if sections[0].changes.is_empty() {
+            sections.remove(0);
+        }
+        sections
+    }
+
+    /// Merge sections for the same author into the first section for that author.
+    pub fn merge_sections(&self) {
+        loop {
+            let sections = self.sections();
+            let duplicate = sections.iter().enumerate().find_map(|(j, section)| {
+                let author = section.author()?;
+                let i = sections[..j]
+                    .iter()
+                    .position(|s| s.author().as_ref() == Some(&author))?;
+                Some((&sections[i], section))
+            });
+            let Some((first, duplicate)) = duplicate else {
+                break;
+            };
+            let lines = duplicate.nodes[1..].to_vec();
+            for node in &lines {
+                node.detach();
+            }
+            insert_at(first.nodes.last().unwrap(), 1, lines);
+            remove_header(&duplicate.nodes[0]);
+        }
+    }
+
+    /// Move a change to the section for an author, adding a section if there is none.
+    ///
+    /// Changes before the first section header are attributed to the maintainer of the
+    /// entry. A nested change becomes a top-level change in the new section: it is
+    /// unindented along with the changes nested below it, and its bullet becomes `*`. The
+    /// section that the change is moved from is kept, even if it is left empty; see
+    /// [`Entry::drop_empty_sections`].
+    pub fn move_change_to_author(&self, change: &Change, author: &str) {
+        let sections = self.sections();
+        let maintainer = self.maintainer();
+        let target = sections
+            .iter()
+            .find(|s| s.author().as_deref() == Some(author))
+            .or_else(|| {
+                sections
+                    .iter()
+                    .find(|s| s.header.is_none() && maintainer.as_deref() == Some(author))
+            });
+        let anchor = match target {
+            Some(section) => section.nodes.last().unwrap().clone(),
+            None => {
+                // Like add_change_for_author, attribute existing changes to the maintainer if
+                // there are no sections yet
+                if let ([untitled], Some(maintainer)) = (sections.as_slice(), maintainer) {
+                    if untitled.header.is_none() {
+                        let header = body_line(&format_section_title(&maintainer));
+                        insert_at(&untitled.nodes[0], 0, vec![header]);
+                    }
+                }
+                let header = body_line(&format_section_title(author));
+                match sections.last() {
+                    Some(last) => insert_at(
+                        last.nodes.last().unwrap(),
+                        1,
+                        vec![body_line(""), header.clone()],
+                    ),
+                    None => self.insert_first_body_line(header.clone()),
+                }
+                header
+            }
+        };
+        change.move_to(&anchor, 1);
+        change.unindent(change.indent());
+        change.set_bullet(Bullet::Asterisk);
+    }
+
+    // Insert a line into an entry without changes, after the blank line below the entry
+    // header, and make sure it is followed by a blank line too.
+    fn insert_first_body_line(&self, line: SyntaxNode) {
+        let mut children = self.syntax().children();
+        let header = children.find(|n| n.kind() == ENTRY_HEADER);
+        let previous = match children.next() {
+            Some(n) if n.kind() == EMPTY_LINE => Some(n),
+            _ => header,
+        };
+        let index = previous.map_or(0, |n| n.index() + 1);
+        self.syntax()
+            .splice_children(index..index, vec![line.clone().into()]);
+        if !line.next_sibling().is_some_and(|n| is_blank(&n)) {
+            insert_at(&line, 1, vec![body_line("")]);
+        }
+    }
+
+    /// Sort the sections by author, with the sections for the uploader first.
+    ///
+    /// Changes before the first section header are left where they are.
+    pub fn sort_sections(&self) {
+        let uploader = self.maintainer();
+        let sections = self
+            .sections()
+            .into_iter()
+            .filter(|s| s.header.is_some())
+            .collect::<Vec<_>>();
+        let mut sorted = sections.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|s| {
+            let author = s.author();
+            (author != uploader, author)
+        });
+
+        // Put the sorted sections where the old sections were, so that the lines in between
+        // (usually blank lines) are kept in place
+        let in_section = |node: &SyntaxNode| sections.iter().any(|s| s.nodes.contains(node));
+        let anchors = sections
+            .iter()
+            .map(|s| s.nodes[0].prev_sibling().filter(|n| !in_section(n)))
+            .collect::<Vec<_>>();
+        for node in sections.iter().flat_map(|s| s.nodes.iter()) {
+            node.detach();
+        }
+        let mut previous: Option<SyntaxNode> = None;
+        for (anchor, section) in anchors.into_iter().zip(sorted) {
+            match anchor.or(previous) {
+                Some(anchor) => insert_at(&anchor, 1, section.nodes.clone()),
+                None => self.syntax().splice_children(
+                    0..0,
+                    section.nodes.iter().cloned().map(|n| n.into()).collect(),
+                ),
+            }
+            previous = section.nodes.last().cloned();
+        }
+    }
+
+    /// Remove the sections that have no changes.
+    pub fn drop_empty_sections(&self) {
+        for section in self.sections() {
+            if let (Some(header), true) = (&section.header, section.changes.is_empty()) {
+                remove_header(header);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::changes::Bullet;
+    use crate::ChangeLog;
+    use rowan::ast::AstNode;
+
+    const CHANGELOG: &str = r#"foo (1.0-1) unstable; urgency=low
+
+  * Initial release.
+
+  [ Jane Example ]
+  * Fix a bug that was
+    found by the tests.
+    - Add a test.
+    - Drop the workaround:
+      + in foo.c
+  * Fix a crash.
+
+    More about the crash.
+
+  [ Joe Example ]
+  * Update the translations.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#;
+
+    #[test]
+    fn test_sections() {
+        let changelog: ChangeLog = CHANGELOG.parse().unwrap();
+        let entry = changelog.entries().next().unwrap();
+        let sections = entry.sections();
+        assert_eq!(
+            sections.iter().map(|s| s.author()).collect::<Vec<_>>(),
+            vec![
+                None,
+                Some("Jane Example".to_string()),
+                Some("Joe Example".to_string())
+            ]
+        );
+        assert_eq!(sections[0].changes()[0].lines(), vec!["* Initial release."]);
+
+        let changes = sections[1].changes();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].bullet(), Some(Bullet::Asterisk));
+        assert_eq!(
+            changes[0].lines(),
+            vec!["* Fix a bug that was", "  found by the tests."]
+        );
+        let children = changes[0].children();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1].bullet(), Some(Bullet::Minus));
+        assert_eq!(children[1].indent(), 2);
+        assert_eq!(children[1].children()[0].lines(), vec!["    + in foo.c"]);
+        assert_eq!(
+            changes[1].lines(),
+            vec!["* Fix a crash.", "", "  More about the crash."]
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let changelog: ChangeLog = CHANGELOG.parse().unwrap();
+        let entry = changelog.entries().next().unwrap();
+        let mut sections = entry.sections();
+        let mut changes = sections.remove(1).changes;
+        changes.remove(0).remove();
+        changes.remove(0).remove();
+        assert_eq!(
+            changelog.to_string(),
+            CHANGELOG.replace(
+                r#"  * Fix a bug that was
+    found by the tests.
+    - Add a test.
+    - Drop the workaround:
+      + in foo.c
+  * Fix a crash.
+
+    More about the crash.
+"#,
+                ""
+            )
+        );
+    }
+
+    #[test]
+    fn test_move() {
+        let changelog: ChangeLog = CHANGELOG.parse().unwrap();
+        let entry = changelog.entries().next().unwrap();
+        let sections = entry.sections();
+        let changes = sections[1].changes();
+        changes[0].move_after(&changes[1]);
+        // Moving a change relative to its own nested changes does nothing
+        changes[0].move_before(&changes[0].children()[1]);
+        changes[0].children()[1].move_before(&changes[0].children()[0]);
+        assert_eq!(
+            changelog.to_string(),
+            CHANGELOG.replace(
+                r#"  * Fix a bug that was
+    found by the tests.
+    - Add a test.
+    - Drop the workaround:
+      + in foo.c
+  * Fix a crash.
+
+    More about the crash.
+"#,
+                r#"  * Fix a crash.
+
+    More about the crash.
+  * Fix a bug that was
+    found by the tests.
+    - Drop the workaround:
+      + in foo.c
+    - Add a test.
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_replace_lines() {
+        let changelog: ChangeLog = CHANGELOG.parse().unwrap();
+        let entry = changelog.entries().next().unwrap();
+        let mut sections = entry.sections();
+        let change = &mut sections[1].changes[0];
+        change.replace_lines(&["* Fix a bug found by the tests."]);
+        assert_eq!(change.lines(), vec!["* Fix a bug found by the tests."]);
+        assert_eq!(
+            changelog.to_string(),
+            CHANGELOG.replace(
+                "  * Fix a bug that was\n    found by the tests.\n",
+                "  * Fix a bug found by the tests.\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_replace_lines_after_children() {
+        let changelog: ChangeLog = r#"foo (1.0-1) unstable; urgency=low
+
+  * A.
+    + Sub.
+    Continued.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        let entry = changelog.entries().next().unwrap();
+        let mut change = entry.sections()[0].changes()[0].clone();
+        change.replace_lines(&["* B.", "  Continued again.", "  More."]);
+        assert_eq!(
+            body(&changelog),
+            vec!["* B.", "  + Sub.", "  Continued again.", "  More."]
+        );
+        assert_eq!(entry.sections()[0].changes()[0], change);
+
+        change.replace_lines(&["* C."]);
+        assert_eq!(body(&changelog), vec!["* C.", "  + Sub."]);
+    }
+
+    #[test]
+    fn test_syntax() {
+        let changelog: ChangeLog = CHANGELOG.parse().unwrap();
+        let entry = changelog.entries().next().unwrap();
+        let sections = entry.sections();
+        assert!(sections[0].header().is_none());
+        assert_eq!(
+            sections[1].header().unwrap().to_string(),
+            "  [ Jane Example ]\n"
+        );
+        let change = &sections[1].changes()[0];
+        assert_eq!(
+            change
+                .syntax()
+                .iter()
+                .map(|n| n.text().to_string())
+                .collect::<Vec<_>>(),
+            vec!["  * Fix a bug that was\n", "    found by the tests.\n"]
+        );
+        assert!(change
+            .syntax()
+            .iter()
+            .all(|n| n.parent().as_ref() == Some(entry.syntax())));
+    }
+
+    fn body(changelog: &ChangeLog) -> Vec<String> {
+        changelog.entries().next().unwrap().change_lines().collect()
+    }
+
+    #[test]
+    fn test_merge_sections() {
+        let changelog: ChangeLog = r#"foo (1.0-1) unstable; urgency=low
+
+  [ Jane Example ]
+  * A.
+
+  [ Joe Example ]
+  * B.
+
+  [ Jane Example ]
+  * C.
+    + D.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        changelog.entries().next().unwrap().merge_sections();
+        assert_eq!(
+            body(&changelog),
+            vec![
+                "[ Jane Example ]",
+                "* A.",
+                "* C.",
+                "  + D.",
+                "",
+                "[ Joe Example ]",
+                "* B."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drop_empty_sections() {
+        let changelog: ChangeLog = r#"foo (1.0-1) unstable; urgency=low
+
+  [ Jane Example ]
+
+  [ Joe Example ]
+  * B.
+
+  [ John Example ]
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        changelog.entries().next().unwrap().drop_empty_sections();
+        assert_eq!(
+            changelog.to_string(),
+            r#"foo (1.0-1) unstable; urgency=low
+
+  [ Joe Example ]
+  * B.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        );
+    }
+
+    #[test]
+    fn test_sort_sections() {
+        let changelog: ChangeLog = r#"foo (1.0-1) unstable; urgency=low
+
+  * Untitled.
+
+  [ Zoe Example ]
+  * A.
+
+  [ Jane Example ]
+  * B.
+
+  [ Joe Example ]
+  * C.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        changelog.entries().next().unwrap().sort_sections();
+        assert_eq!(
+            changelog.to_string(),
+            r#"foo (1.0-1) unstable; urgency=low
+
+  * Untitled.
+
+  [ Joe Example ]
+  * C.
+
+  [ Jane Example ]
+  * B.
+
+  [ Zoe Example ]
+  * A.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        );
+    }
+
+    #[test]
+    fn test_move_change_to_author() {
+        let changelog: ChangeLog = r#"foo (1.0-1) unstable; urgency=low
+
+  * A.
+  * B.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        let entry = changelog.entries().next().unwrap();
+        let sections = entry.sections();
+        entry.move_change_to_author(&sections[0].changes()[0], "Jane Example");
+        assert_eq!(
+            body(&changelog),
+            vec!["[ Joe Example ]", "* B.", "", "[ Jane Example ]", "* A."]
+        );
+
+        let sections = entry.sections();
+        entry.move_change_to_author(&sections[1].changes()[0], "Joe Example");
+        entry.drop_empty_sections();
+        assert_eq!(body(&changelog), vec!["[ Joe Example ]", "* B.", "* A."]);
+    }
+
+    #[test]
+    fn test_move_nested_change_to_author() {
+        let changelog: ChangeLog = r#"foo (1.0-1) unstable; urgency=low
+
+  * A.
+    + Sub, which is
+      wrapped.
+      - Subsub.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        let entry = changelog.entries().next().unwrap();
+        let sections = entry.sections();
+        let nested = &sections[0].changes()[0].children()[0];
+        entry.move_change_to_author(nested, "Jane Example");
+        assert_eq!(
+            body(&changelog),
+            vec![
+                "[ Joe Example ]",
+                "* A.",
+                "",
+                "[ Jane Example ]",
+                "* Sub, which is",
+                "  wrapped.",
+                "  - Subsub.",
+            ]
+        );
+        assert_eq!(nested.indent(), 0);
+        assert_eq!(nested.bullet(), Some(Bullet::Asterisk));
+
+        let sections = entry.sections();
+        assert_eq!(sections[1].changes().len(), 1);
+        assert_eq!(sections[1].changes()[0].children().len(), 1);
+    }
+
+    #[test]
+    fn test_move_change_to_author_in_empty_entry() {
+        let changelog: ChangeLog = r#"foo (1.0-2) unstable; urgency=low
+
+ -- Joe Example <joe@example.com>  Tue, 05 Sep 2023 18:13:45 -0500
+
+foo (1.0-1) unstable; urgency=low
+
+  * A.
+  * B.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        .parse()
+        .unwrap();
+        let entries = changelog.entries().collect::<Vec<_>>();
+        let sections = entries[1].sections();
+        entries[0].move_change_to_author(&sections[0].changes()[0], "Jane Example");
+        assert_eq!(
+            changelog.to_string(),
+            r#"foo (1.0-2) unstable; urgency=low
+
+  [ Jane Example ]
+  * A.
+
+ -- Joe Example <joe@example.com>  Tue, 05 Sep 2023 18:13:45 -0500
+
+foo (1.0-1) unstable; urgency=low
+
+  * B.
+
+ -- Joe Example <joe@example.com>  Mon, 04 Sep 2023 18:13:45 -0500
+"#
+        );
+    }
+}
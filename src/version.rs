@@ -0,0 +1,247 @@
+//! Strategies for computing the version of a new changelog entry.
+//!
+//! See the Debian Developer's Reference for the conventions used here.
+//!
+//! # Example
+//!
+//! ```
+//! use debian_changelog::version::VersionBump;
+//!
+//! let previous = "1.0-1".parse().unwrap();
+//! assert_eq!(VersionBump::NonMaintainerUpload.next_version(&previous).to_string(), "1.0-1.1");
+//! assert_eq!(VersionBump::Backport(12).next_version(&previous).to_string(), "1.0-1~bpo12+1");
+//! assert_eq!(VersionBump::StableUpdate(12).next_version(&previous).to_string(), "1.0-1+deb12u1");
+//! ```
+
+use crate::distributions::{DistributionRegistry, Pocket, Vendor};
+use debversion::Version;
+
+/// How to derive the version of a new entry from that of the previous entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum VersionBump {
+    /// Increment the Debian revision, or the upstream version for native packages,
+    /// e.g. 1.0-1 => 1.0-2.
+    #[default]
+    Debian,
+    /// A new upstream release, e.g. 1.0-3 => 1.1-1.
+    NewUpstream(String),
+    /// A non-maintainer upload, e.g. 1.0-1 => 1.0-1.1, or 1.0 => 1.0+nmu1 for native packages.
+    NonMaintainerUpload,
+    /// A backport to the Debian release with the given number, e.g. 1.0-1 => 1.0-1~bpo12+1.
+    ///
+    /// A backport to another release is replaced, e.g. 1.0-1~bpo11+1 => 1.0-1~bpo12+1.
+    Backport(u32),
+    /// A stable or security update for the Debian release with the given number,
+    /// e.g. 1.0-1 => 1.0-1+deb12u1.
+    ///
+    /// An update for another release is replaced, e.g. 1.0-1+deb11u2 => 1.0-1+deb12u1.
+    StableUpdate(u32),
+    /// A binary-only non-maintainer upload, e.g. 1.0-1 => 1.0-1+b1.
+    BinNmu,
+    /// An Ubuntu upload, e.g. 1.0-1 => 1.0-1ubuntu1.
+    ///
+    /// A no-change rebuild is replaced, e.g. 1.0-1build1 => 1.0-1ubuntu1.
+    Ubuntu,
+    /// A new upstream release that requires an epoch bump, e.g. 2.0-1 => 1:1.0-1.
+    Epoch(String),
+}
+
+/// Increment the number after the last occurrence of `prefix` at the end of `s`,
+/// or append `prefix` followed by 1 if there is none.
+fn bump_suffix(s: &str, prefix: &str) -> String {
+    if let Some(pos) = s.rfind(prefix) {
+        let number = &s[pos + prefix.len()..];
+        if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) {
+            if let Ok(number) = number.parse::<u64>() {
+                return format!("{}{}{}", &s[..pos], prefix, number + 1);
+            }
+        }
+    }
+    format!("{}{}1", s, prefix)
+}
+
+impl VersionBump {
+    /// Pick the bump for an upload to the given distribution, e.g. `Backport(12)` for
+    /// "bookworm-backports".
+    ///
+    /// Returns `None` if the distribution is not known.
+    pub fn for_distribution(
+        registry: &DistributionRegistry,
+        distribution: &str,
+    ) -> Option<VersionBump> {
+        let target = registry.lookup(distribution)?;
+        if target.vendor == Vendor::Ubuntu {
+            return Some(VersionBump::Ubuntu);
+        }
+        let release = registry.release(&target.series)?;
+        let today = crate::reproducible::now().date_naive();
+        Some(match (target.pocket, release.major_version()) {
+            (Some(Pocket::Backports | Pocket::BackportsSloppy), Some(n)) => {
+                VersionBump::Backport(n)
+            }
+            (Some(_), Some(n)) => VersionBump::StableUpdate(n),
+            (None, Some(n)) if release.is_released(today) => VersionBump::StableUpdate(n),
+            _ => VersionBump::Debian,
+        })
+    }
+
+    /// Compute the version that follows `previous`.
+    pub fn next_version(&self, previous: &Version) -> Version {
+        let mut version = previous.clone();
+        // The last component of the version, i.e. the one that suffixes are added to
+        let last = |version: &mut Version, f: &dyn Fn(&str) -> String| match version
+            .debian_revision
+            .as_mut()
+        {
+            Some(revision) => *revision = f(revision),
+            None => version.upstream_version = f(version.upstream_version.as_str()),
+        };
+        match self {
+            VersionBump::Debian => version.increment_debian(),
+            VersionBump::NewUpstream(upstream_version) => {
+                version.upstream_version = upstream_version.clone();
+                if version.debian_revision.is_some() {
+                    version.debian_revision = Some("1".to_string());
+                }
+            }
+            VersionBump::NonMaintainerUpload => match version.debian_revision.as_mut() {
+                Some(revision) => *revision = bump_suffix(revision, "."),
+                None => version.upstream_version = bump_suffix(&version.upstream_version, "+nmu"),
+            },
+            VersionBump::Backport(release) => last(&mut version, &|s| {
+                // Backports are rebased on the version in unstable, not on other backports
+                let s = match lazy_regex::regex_captures!(r"^(.*)~bpo(\d+)\+\d+$", s) {
+                    Some((_, base, other)) if other != release.to_string() => base,
+                    _ => s,
+                };
+                bump_suffix(s, &format!("~bpo{}+", release))
+            }),
+            VersionBump::StableUpdate(release) => last(&mut version, &|s| {
+                // Like backports, updates are based on the version without another update
+                let s = match lazy_regex::regex_captures!(r"^(.*)\+deb(\d+)u\d+$", s) {
+                    Some((_, base, other)) if other != release.to_string() => base,
+                    _ => s,
+                };
+                bump_suffix(s, &format!("+deb{}u", release))
+            }),
+            VersionBump::BinNmu => last(&mut version, &|s| bump_suffix(s, "+b")),
+            VersionBump::Ubuntu => last(&mut version, &|s| {
+                // A no-change rebuild is superseded by the next Ubuntu upload
+                let s = lazy_regex::regex_replace!(r"build\d+$", s, "");
+                bump_suffix(&s, "ubuntu")
+            }),
+            VersionBump::Epoch(upstream_version) => {
+                version.epoch = Some(previous.epoch.unwrap_or(0) + 1);
+                version.upstream_version = upstream_version.clone();
+                if version.debian_revision.is_some() {
+                    version.debian_revision = Some("1".to_string());
+                }
+            }
+        }
+        version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(bump: VersionBump, previous: &str) -> String {
+        bump.next_version(&previous.parse().unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_debian() {
+        assert_eq!(next(VersionBump::Debian, "1.0-1"), "1.0-2");
+        assert_eq!(next(VersionBump::Debian, "1.0"), "1.1");
+    }
+
+    #[test]
+    fn test_new_upstream() {
+        assert_eq!(
+            next(VersionBump::NewUpstream("1.1".into()), "1:1.0-3"),
+            "1:1.1-1"
+        );
+        assert_eq!(next(VersionBump::NewUpstream("1.1".into()), "1.0"), "1.1");
+    }
+
+    #[test]
+    fn test_nmu() {
+        assert_eq!(next(VersionBump::NonMaintainerUpload, "1.0-1"), "1.0-1.1");
+        assert_eq!(next(VersionBump::NonMaintainerUpload, "1.0-1.1"), "1.0-1.2");
+        assert_eq!(next(VersionBump::NonMaintainerUpload, "1.0"), "1.0+nmu1");
+        assert_eq!(
+            next(VersionBump::NonMaintainerUpload, "1.0+nmu1"),
+            "1.0+nmu2"
+        );
+    }
+
+    #[test]
+    fn test_backport() {
+        assert_eq!(next(VersionBump::Backport(12), "1.0-1"), "1.0-1~bpo12+1");
+        assert_eq!(
+            next(VersionBump::Backport(12), "1.0-1~bpo12+1"),
+            "1.0-1~bpo12+2"
+        );
+        assert_eq!(
+            next(VersionBump::Backport(12), "1.0-1~bpo11+1"),
+            "1.0-1~bpo12+1"
+        );
+        assert_eq!(next(VersionBump::Backport(12), "1.0"), "1.0~bpo12+1");
+    }
+
+    #[test]
+    fn test_stable_update() {
+        assert_eq!(
+            next(VersionBump::StableUpdate(12), "1.0-1"),
+            "1.0-1+deb12u1"
+        );
+        assert_eq!(
+            next(VersionBump::StableUpdate(12), "1.0-1+deb12u1"),
+            "1.0-1+deb12u2"
+        );
+        assert_eq!(
+            next(VersionBump::StableUpdate(12), "1.0-1+deb11u2"),
+            "1.0-1+deb12u1"
+        );
+    }
+
+    #[test]
+    fn test_binnmu() {
+        assert_eq!(next(VersionBump::BinNmu, "1.0-1"), "1.0-1+b1");
+        assert_eq!(next(VersionBump::BinNmu, "1.0-1+b1"), "1.0-1+b2");
+        assert_eq!(next(VersionBump::BinNmu, "1.0"), "1.0+b1");
+    }
+
+    #[test]
+    fn test_ubuntu() {
+        assert_eq!(next(VersionBump::Ubuntu, "1.0-1"), "1.0-1ubuntu1");
+        assert_eq!(next(VersionBump::Ubuntu, "1.0-1ubuntu1"), "1.0-1ubuntu2");
+        assert_eq!(next(VersionBump::Ubuntu, "1.0-1build1"), "1.0-1ubuntu1");
+        assert_eq!(
+            next(VersionBump::Ubuntu, "1.0-1ubuntu1build2"),
+            "1.0-1ubuntu2"
+        );
+    }
+
+    #[test]
+    fn test_for_distribution() {
+        let registry = DistributionRegistry::builtin();
+        let bump = |d| VersionBump::for_distribution(&registry, d);
+        assert_eq!(bump("bookworm-backports"), Some(VersionBump::Backport(12)));
+        assert_eq!(
+            bump("bookworm-security"),
+            Some(VersionBump::StableUpdate(12))
+        );
+        assert_eq!(bump("bullseye"), Some(VersionBump::StableUpdate(11)));
+        assert_eq!(bump("unstable"), Some(VersionBump::Debian));
+        assert_eq!(bump("jammy-updates"), Some(VersionBump::Ubuntu));
+        assert_eq!(bump("bookwrom"), None);
+    }
+
+    #[test]
+    fn test_epoch() {
+        assert_eq!(next(VersionBump::Epoch("0.9".into()), "1.0-1"), "1:0.9-1");
+        assert_eq!(next(VersionBump::Epoch("0.9".into()), "1:1.0-1"), "2:0.9-1");
+    }
+}